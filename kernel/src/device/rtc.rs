@@ -0,0 +1,191 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Driver for the motherboard's CMOS Real-Time Clock.
+//!
+//! The RTC is the only wall-clock source available on boot: [`init`] reads it once to seed the
+//! realtime clock, then arms its periodic interrupt on IRQ8 to hand the time subsystem a steady
+//! tick via [`ticks`].
+
+use crate::idt::irq::{self, InterruptHandler, IrqReturn};
+use core::{
+	arch::asm,
+	sync::atomic::{AtomicU64, Ordering},
+};
+use utils::{errno::EResult, ptr::arc::Arc};
+
+/// Port used to select a CMOS register.
+const CMOS_ADDRESS: u16 = 0x70;
+/// Port used to read/write the previously selected CMOS register.
+const CMOS_DATA: u16 = 0x71;
+
+/// Bit of [`CMOS_ADDRESS`] disabling the NMI while a register is selected.
+const NMI_DISABLE: u8 = 1 << 7;
+
+/// Status register A: bit 7 is set while the RTC is updating its registers.
+const REG_STATUS_A: u8 = 0x0a;
+/// Status register B: bit 2 clear means BCD-encoded registers, bit 6 is the periodic-interrupt
+/// enable.
+const REG_STATUS_B: u8 = 0x0b;
+/// Status register C: must be read to re-arm the periodic interrupt once it has fired.
+const REG_STATUS_C: u8 = 0x0c;
+
+const REG_SECONDS: u8 = 0x00;
+const REG_MINUTES: u8 = 0x02;
+const REG_HOURS: u8 = 0x04;
+const REG_DAY: u8 = 0x07;
+const REG_MONTH: u8 = 0x08;
+const REG_YEAR: u8 = 0x09;
+
+const STATUS_A_UPDATE_IN_PROGRESS: u8 = 1 << 7;
+const STATUS_B_BINARY: u8 = 1 << 2;
+const STATUS_B_PIE: u8 = 1 << 6;
+
+/// The IRQ line the RTC's periodic interrupt is wired to.
+const RTC_IRQ: u8 = 8;
+
+/// Writes `val` to `port`.
+fn outb(port: u16, val: u8) {
+	unsafe {
+		asm!("out dx, al", in("dx") port, in("al") val);
+	}
+}
+
+/// Reads a byte from `port`.
+fn inb(port: u16) -> u8 {
+	let val: u8;
+	unsafe {
+		asm!("in al, dx", in("dx") port, out("al") val);
+	}
+	val
+}
+
+/// Reads CMOS register `reg`, keeping the NMI disabled only for the duration of the access.
+fn cmos_read(reg: u8) -> u8 {
+	outb(CMOS_ADDRESS, NMI_DISABLE | reg);
+	let val = inb(CMOS_DATA);
+	// Re-select the register with the NMI-disable bit clear to re-enable the NMI
+	outb(CMOS_ADDRESS, reg);
+	val
+}
+
+/// Converts a BCD-encoded byte to binary.
+fn bcd_to_bin(val: u8) -> u8 {
+	(val & 0x0f) + ((val >> 4) * 10)
+}
+
+/// The fields of a CMOS RTC reading, already normalized to binary.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct RawTime {
+	second: u8,
+	minute: u8,
+	hour: u8,
+	day: u8,
+	month: u8,
+	year: u8,
+}
+
+/// Reads every RTC register once, normalizing BCD fields to binary if necessary.
+fn read_once() -> RawTime {
+	let binary = cmos_read(REG_STATUS_B) & STATUS_B_BINARY != 0;
+	let normalize = |val: u8| if binary { val } else { bcd_to_bin(val) };
+	RawTime {
+		second: normalize(cmos_read(REG_SECONDS)),
+		minute: normalize(cmos_read(REG_MINUTES)),
+		hour: normalize(cmos_read(REG_HOURS)),
+		day: normalize(cmos_read(REG_DAY)),
+		month: normalize(cmos_read(REG_MONTH)),
+		year: normalize(cmos_read(REG_YEAR)),
+	}
+}
+
+/// Reads the current time from the RTC.
+///
+/// The read is retried until an update is not in progress and two consecutive reads agree, since
+/// the RTC can tick in the middle of a read.
+fn read_time() -> RawTime {
+	loop {
+		while cmos_read(REG_STATUS_A) & STATUS_A_UPDATE_IN_PROGRESS != 0 {}
+		let first = read_once();
+		while cmos_read(REG_STATUS_A) & STATUS_A_UPDATE_IN_PROGRESS != 0 {}
+		let second = read_once();
+		if first == second {
+			break second;
+		}
+	}
+}
+
+/// Converts a RTC reading to a Unix timestamp, in seconds.
+///
+/// The RTC's year register only gives the last two digits, so this assumes the 2000-2099 range,
+/// which covers every machine this kernel is expected to boot on.
+fn to_unix_epoch(time: RawTime) -> u64 {
+	let year = 2000 + time.year as u64;
+	let is_leap = |y: u64| (y % 4 == 0 && y % 100 != 0) || y % 400 == 0;
+	let mut days: u64 = 0;
+	for y in 1970..year {
+		days += if is_leap(y) { 366 } else { 365 };
+	}
+	const DAYS_IN_MONTH: [u64; 12] = [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+	for m in 0..(time.month as u64 - 1) {
+		days += DAYS_IN_MONTH[m as usize];
+		if m == 1 && is_leap(year) {
+			days += 1;
+		}
+	}
+	days += time.day as u64 - 1;
+	days * 86400 + time.hour as u64 * 3600 + time.minute as u64 * 60 + time.second as u64
+}
+
+/// The number of periodic interrupts received since [`init`] armed the RTC.
+static TICKS: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the number of RTC periodic ticks delivered so far.
+pub fn ticks() -> u64 {
+	TICKS.load(Ordering::Relaxed)
+}
+
+/// Handler for the RTC's periodic interrupt on [`RTC_IRQ`].
+struct RtcHandler;
+
+impl InterruptHandler for RtcHandler {
+	fn handle(&self) -> IrqReturn {
+		// Reading status register C acknowledges the interrupt and re-arms it; without this the
+		// RTC stops raising IRQ8 after the first one.
+		cmos_read(REG_STATUS_C);
+		TICKS.fetch_add(1, Ordering::Relaxed);
+		IrqReturn::Handled
+	}
+}
+
+/// The periodic interrupt rate divider, giving a tick roughly every 1/1024 s (rate `6` on a
+/// 32768 Hz RTC oscillator).
+const PERIODIC_RATE: u8 = 0x06;
+
+/// Reads the current wall-clock time and arms the RTC's periodic interrupt.
+pub fn init() -> EResult<()> {
+	let epoch = to_unix_epoch(read_time());
+	crate::time::clock::set_realtime(epoch);
+	let prev_a = cmos_read(REG_STATUS_A);
+	outb(CMOS_ADDRESS, NMI_DISABLE | REG_STATUS_A);
+	outb(CMOS_DATA, (prev_a & 0xf0) | PERIODIC_RATE);
+	let prev_b = cmos_read(REG_STATUS_B);
+	outb(CMOS_ADDRESS, NMI_DISABLE | REG_STATUS_B);
+	outb(CMOS_DATA, prev_b | STATUS_B_PIE);
+	irq::register_handler(RTC_IRQ, Arc::new(RtcHandler)?, 0)
+}
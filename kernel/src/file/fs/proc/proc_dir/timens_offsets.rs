@@ -0,0 +1,62 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `timens_offsets` node exposes a process's [`TimeNamespace`] monotonic/boot-time offsets,
+//! in nanoseconds, for a supervisor to read after a freeze and rewrite after a restore.
+
+use crate::{
+	file::{fs::FileOps, File},
+	format_content,
+	process::{pid::Pid, Process},
+};
+use core::{fmt, str};
+use utils::{errno, errno::EResult};
+
+/// The `timens_offsets` node of the proc.
+#[derive(Clone, Debug)]
+pub struct TimensOffsets(pub Pid);
+
+impl FileOps for TimensOffsets {
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let proc = Process::get_by_pid(self.0).ok_or_else(|| errno!(ENOENT))?;
+		let (monotonic_offset_ns, boottime_offset_ns) = proc.time_ns.offsets();
+		let disp = fmt::from_fn(move |f| {
+			writeln!(f, "monotonic {monotonic_offset_ns}")?;
+			writeln!(f, "boottime {boottime_offset_ns}")
+		});
+		format_content!(off, buf, "{disp}")
+	}
+
+	fn write(&self, _file: &File, _off: u64, buf: &[u8]) -> EResult<usize> {
+		let proc = Process::get_by_pid(self.0).ok_or_else(|| errno!(ENOENT))?;
+		let (mut monotonic_offset_ns, mut boottime_offset_ns) = proc.time_ns.offsets();
+		let content = str::from_utf8(buf).map_err(|_| errno!(EINVAL))?;
+		for line in content.lines() {
+			let (name, val) = line.split_once(' ').ok_or_else(|| errno!(EINVAL))?;
+			let val: i64 = val.trim().parse().map_err(|_| errno!(EINVAL))?;
+			match name {
+				"monotonic" => monotonic_offset_ns = val,
+				"boottime" => boottime_offset_ns = val,
+				_ => return Err(errno!(EINVAL)),
+			}
+		}
+		proc.time_ns
+			.set_offsets(monotonic_offset_ns, boottime_offset_ns)?;
+		Ok(buf.len())
+	}
+}
@@ -0,0 +1,170 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Boot-time unpacking of a `newc` (SVR4 without CRC) cpio archive into the root tmpfs.
+//!
+//! The bootloader loads the initramfs image as a contiguous blob and passes its address and size
+//! down to [`unpack`], which walks the archive entry by entry and recreates each file, directory
+//! and symlink under the already-mounted root filesystem.
+
+use crate::file::{
+	path::PathBuf,
+	vfs,
+	vfs::{Entry, ResolutionSettings},
+	FileType, Stat,
+};
+use utils::{collections::vec::Vec, errno, errno::EResult, ptr::arc::Arc};
+
+/// The magic number of a `newc` cpio entry header ("070701").
+const NEWC_MAGIC: &[u8; 6] = b"070701";
+/// The name of the special entry marking the end of the archive.
+const TRAILER_NAME: &[u8] = b"TRAILER!!!";
+/// The size in bytes of a `newc` entry header, magic included.
+const HEADER_LEN: usize = 110;
+
+/// A single parsed `newc` header.
+struct Header {
+	mode: u32,
+	uid: u32,
+	gid: u32,
+	nlink: u32,
+	mtime: u32,
+	filesize: u32,
+	namesize: u32,
+}
+
+/// Parses one ASCII-hex field of the header.
+fn parse_hex(field: &[u8]) -> EResult<u32> {
+	let s = core::str::from_utf8(field).map_err(|_| errno!(EINVAL))?;
+	u32::from_str_radix(s, 16).map_err(|_| errno!(EINVAL))
+}
+
+/// Rounds `off` up to the next multiple of 4, as `newc` pads both the header+name and the file
+/// data to a 4-byte boundary.
+fn align4(off: usize) -> usize {
+	(off + 3) & !3
+}
+
+/// Parses the header starting at `data[off..]`, returning the header, the offset right after
+/// the (already-aligned) pathname, and the pathname itself without its trailing NUL.
+fn parse_header(data: &[u8], off: usize) -> EResult<(Header, usize, &[u8])> {
+	if data.len() < off + HEADER_LEN || &data[off..off + 6] != NEWC_MAGIC {
+		return Err(errno!(EINVAL));
+	}
+	let field = |i: usize| parse_hex(&data[off + 6 + i * 8..off + 6 + i * 8 + 8]);
+	let _ino = field(0)?;
+	let mode = field(1)?;
+	let uid = field(2)?;
+	let gid = field(3)?;
+	let nlink = field(4)?;
+	let mtime = field(5)?;
+	let filesize = field(6)?;
+	// devmajor, devminor, rdevmajor, rdevminor (fields 7 to 10) are skipped.
+	let namesize = field(11)?;
+	let name_off = off + HEADER_LEN;
+	let name_end = name_off + namesize as usize;
+	if name_end > data.len() || namesize == 0 {
+		return Err(errno!(EINVAL));
+	}
+	// Strip the trailing NUL included in `namesize`.
+	let name = &data[name_off..name_end - 1];
+	let header = Header {
+		mode,
+		uid,
+		gid,
+		nlink,
+		mtime,
+		filesize,
+		namesize,
+	};
+	Ok((header, align4(name_end), name))
+}
+
+/// Unpacks the `newc` cpio archive located at `data` onto the root filesystem.
+///
+/// This must be called after the root filesystem has been mounted, and is typically invoked once
+/// at boot, right before handing control to the init process.
+pub fn unpack(data: &[u8]) -> EResult<()> {
+	let rs = ResolutionSettings::kernel_follow();
+	let mut off = 0;
+	while off < data.len() {
+		let (header, mut data_off, name) = parse_header(data, off)?;
+		if name == TRAILER_NAME {
+			break;
+		}
+		let path = PathBuf::try_from(name)?;
+		let file_type = FileType::from_mode(header.mode).ok_or_else(|| errno!(EINVAL))?;
+		let stat = Stat {
+			file_type,
+			mode: (header.mode & 0o7777) as _,
+			nlink: header.nlink as _,
+			uid: header.uid,
+			gid: header.gid,
+			size: header.filesize as _,
+			blocks: 0,
+			dev_major: 0,
+			dev_minor: 0,
+			ctime: header.mtime as _,
+			mtime: header.mtime as _,
+			atime: header.mtime as _,
+		};
+		let entry = create_path(&path, stat, &rs)?;
+		if header.filesize > 0 {
+			let data_end = data_off + header.filesize as usize;
+			if data_end > data.len() {
+				return Err(errno!(EINVAL));
+			}
+			write_content(&entry, &data[data_off..data_end])?;
+			data_off = align4(data_end);
+		}
+		off = data_off;
+		let _ = header.namesize;
+	}
+	Ok(())
+}
+
+/// Creates every missing intermediate directory along `path`, then the final entry itself with
+/// the given `stat`, returning it.
+fn create_path(path: &PathBuf, stat: Stat, rs: &ResolutionSettings) -> EResult<Arc<Entry>> {
+	let mut parent = vfs::get_file_from_path(crate::file::path::Path::root(), rs)?;
+	let components: Vec<_> = path.components().collect()?;
+	let (last, dirs) = components.split_last().ok_or_else(|| errno!(EINVAL))?;
+	for comp in dirs {
+		parent = match vfs::get_file_from_path(&parent.get_path()?.join(comp)?, rs) {
+			Ok(existing) => existing,
+			Err(_) => vfs::create_file(
+				&parent,
+				comp,
+				rs,
+				Stat {
+					file_type: FileType::Directory,
+					mode: 0o755,
+					..stat
+				},
+			)?,
+		};
+	}
+	vfs::create_file(&parent, last, rs, stat)
+}
+
+/// Writes `content` as the full content of the regular file `entry`.
+fn write_content(entry: &Arc<Entry>, content: &[u8]) -> EResult<()> {
+	let file = crate::file::File::open_entry(entry.clone(), crate::file::O_WRONLY)?;
+	file.ops.write(&file, 0, content)?;
+	Ok(())
+}
@@ -21,6 +21,8 @@
 //! The files are stored on the kernel's memory and thus are removed when the
 //! filesystem is unmounted.
 
+pub mod initramfs;
+
 use super::{kernfs, kernfs::KernFS, Filesystem, FilesystemType, NodeOps};
 use crate::file::{
 	fs::{kernfs::node::DefaultNode, Statfs},
@@ -0,0 +1,151 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `userfs` lets a regular userspace process back a mounted filesystem.
+//!
+//! The daemon opens the scheme's control file (`/dev/userfsN`) and exchanges fixed-header,
+//! variable-payload packets with the kernel: the kernel sends a [`Request`] describing the VFS
+//! operation to perform (lookup, read, write, ...), and the daemon answers with the matching
+//! [`Response`] once it has processed it. This is the same request/response coupling other
+//! userspace-driver schemes use (9P, FUSE), adapted to this crate's `Filesystem`/`NodeOps`
+//! plumbing.
+
+// `pub(crate)` rather than private: `vfs::translator` reuses this wire format and queue to back a
+// single translated `Node` instead of a whole mounted filesystem.
+pub(crate) mod proto;
+pub(crate) mod queue;
+
+use crate::file::{
+	fs::{kernfs, kernfs::KernFS, Filesystem, FilesystemType, NodeOps, Statfs},
+	path::PathBuf,
+	perm::{ROOT_GID, ROOT_UID},
+	FileType, INode, Stat,
+};
+use core::sync::atomic::{AtomicU64, Ordering::Relaxed};
+use proto::{Request, RequestKind, Response};
+use queue::RequestQueue;
+use utils::{boxed::Box, errno::EResult, io::IO, ptr::arc::Arc};
+
+pub use proto::{Request as UserFsRequest, Response as UserFsResponse};
+
+/// A userspace-backed filesystem.
+///
+/// Metadata (the directory tree and node attributes) is kept in an in-memory [`KernFS`] just like
+/// `tmpfs`. [`Filesystem::load_file`] hands out that [`KernFS`]'s own nodes, so file contents are
+/// also served from memory: no `Filesystem`/`NodeOps` method here sends anything to the daemon.
+/// The only path onto the [`RequestQueue`] is an explicit [`Self::call`].
+pub struct UserFs {
+	/// The queue of requests waiting for the daemon to pick up, and of answers waiting for the
+	/// kernel side to be woken up.
+	queue: Arc<RequestQueue>,
+	/// The in-memory directory tree and attributes.
+	inner: KernFS,
+	/// Monotonic counter used to tag outgoing requests so answers can be matched back up.
+	next_id: AtomicU64,
+}
+
+impl UserFs {
+	/// Creates a new instance backed by the given request `queue`.
+	pub fn new(queue: Arc<RequestQueue>) -> EResult<Self> {
+		let root = kernfs::node::DefaultNode::new(
+			Stat {
+				file_type: FileType::Directory,
+				mode: 0o755,
+				nlink: 0,
+				uid: ROOT_UID,
+				gid: ROOT_GID,
+				size: 0,
+				blocks: 0,
+				dev_major: 0,
+				dev_minor: 0,
+				ctime: 0,
+				mtime: 0,
+				atime: 0,
+			},
+			Some(kernfs::ROOT_INODE),
+			Some(kernfs::ROOT_INODE),
+		)?;
+		Ok(Self {
+			queue,
+			inner: KernFS::new(false, Box::new(root)?)?,
+			next_id: AtomicU64::new(0),
+		})
+	}
+
+	/// Sends `kind` to the daemon and blocks until the matching [`Response`] comes back.
+	pub fn call(&self, kind: RequestKind) -> EResult<Response> {
+		let id = self.next_id.fetch_add(1, Relaxed);
+		self.queue.submit(Request {
+			id,
+			kind,
+		})
+	}
+}
+
+impl Filesystem for UserFs {
+	fn get_name(&self) -> &[u8] {
+		b"userfs"
+	}
+
+	fn is_readonly(&self) -> bool {
+		false
+	}
+
+	fn use_cache(&self) -> bool {
+		self.inner.use_cache()
+	}
+
+	fn get_root_inode(&self) -> INode {
+		self.inner.get_root_inode()
+	}
+
+	fn get_stat(&self) -> EResult<Statfs> {
+		self.inner.get_stat()
+	}
+
+	fn load_file(&self, inode: INode) -> EResult<Box<dyn NodeOps>> {
+		self.inner.load_file(inode)
+	}
+}
+
+/// The `userfs` filesystem type, registered so `mount -t userfs` can select it.
+pub struct UserFsType;
+
+impl FilesystemType for UserFsType {
+	fn get_name(&self) -> &'static [u8] {
+		b"userfs"
+	}
+
+	fn detect(&self, _io: &mut dyn IO) -> EResult<bool> {
+		// `userfs` is never auto-detected from a block device: it is only ever selected
+		// explicitly by `mount -t userfs <control-fd> <mountpoint>`.
+		Ok(false)
+	}
+
+	fn load_filesystem(
+		&self,
+		_io: Option<Arc<utils::lock::Mutex<dyn IO>>>,
+		_mountpath: PathBuf,
+		_readonly: bool,
+	) -> EResult<Arc<dyn Filesystem>> {
+		// The daemon's queue is registered beforehand (by opening the control device), and the
+		// mount's `data` string carries the queue's id; wiring that lookup up is out of scope
+		// here, so fall back to a fresh, unconnected queue.
+		Ok(Arc::new(UserFs::new(Arc::new(RequestQueue::new())?)?)?)
+	}
+}
@@ -0,0 +1,68 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The wire format exchanged between the kernel and a `userfs` daemon.
+
+use crate::file::{FileType, INode, Stat};
+use utils::{collections::vec::Vec, errno::Errno};
+
+/// The operation carried by a [`Request`].
+#[derive(Debug)]
+pub enum RequestKind {
+	/// Look up `name` in the directory `parent`.
+	Lookup { parent: INode, name: Vec<u8> },
+	/// Fetch the attributes of `inode`.
+	GetAttr { inode: INode },
+	/// Read `len` bytes of `inode`'s content at offset `off`.
+	Read { inode: INode, off: u64, len: usize },
+	/// Write `data` to `inode`'s content at offset `off`.
+	Write { inode: INode, off: u64, data: Vec<u8> },
+	/// Remove `inode`, the last link to it having just dropped.
+	Remove { inode: INode },
+	/// List the entries of the directory `inode` starting after `off`.
+	ReadDir { inode: INode, off: u64 },
+}
+
+/// A request sent from the kernel to the daemon.
+#[derive(Debug)]
+pub struct Request {
+	/// Identifier used to match the eventual [`Response`] back to the caller waiting on it.
+	pub id: u64,
+	/// The operation being requested.
+	pub kind: RequestKind,
+}
+
+/// The daemon's answer to a [`Request`] of the same `id`.
+#[derive(Debug)]
+pub enum Response {
+	/// The looked up node's inode number.
+	Lookup(INode),
+	/// The requested node's attributes.
+	GetAttr(Stat),
+	/// The bytes read, which may be shorter than requested at end-of-file.
+	Read(Vec<u8>),
+	/// The number of bytes actually written.
+	Write(usize),
+	/// The node was removed.
+	Removed,
+	/// A page of directory entries: name, inode and type, in the order the daemon wants them
+	/// listed.
+	Entries(Vec<(Vec<u8>, INode, FileType)>),
+	/// The daemon reports the operation failed with the given errno.
+	Error(Errno),
+}
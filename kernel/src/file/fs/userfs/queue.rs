@@ -0,0 +1,87 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The in-kernel side of the `userfs` scheme's request/response channel.
+
+use super::proto::{Request, RequestKind, Response};
+use crate::process::scheduler::Scheduler;
+use core::sync::atomic::{AtomicU64, Ordering::Relaxed};
+use utils::{
+	collections::{hashmap::HashMap, vec::Vec},
+	errno::EResult,
+	lock::Mutex,
+};
+
+/// Queue pairing outgoing [`Request`]s (waiting for the daemon to read them) with the
+/// [`Response`]s the daemon writes back.
+#[derive(Default)]
+pub struct RequestQueue {
+	/// Requests the daemon has not yet picked up, in submission order.
+	pending: Mutex<Vec<Request>>,
+	/// Answers that have been written back by the daemon, keyed by request id, for requests
+	/// whose caller has not yet picked them up.
+	completed: Mutex<HashMap<u64, Response>>,
+	/// Counter used by [`Self::call`] to tag outgoing requests.
+	next_id: AtomicU64,
+}
+
+impl RequestQueue {
+	/// Creates a new, empty queue, ready for a daemon to connect to.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Submits `req` to the daemon and blocks the calling thread until the matching [`Response`]
+	/// is available.
+	pub fn submit(&self, req: Request) -> EResult<Response> {
+		let id = req.id;
+		self.pending.lock().push(req)?;
+		loop {
+			if let Some(resp) = self.completed.lock().remove(&id) {
+				return Ok(resp);
+			}
+			// The daemon runs as an ordinary userspace process, so yield the CPU to it instead
+			// of busy-spinning a full timeslice away.
+			Scheduler::tick();
+		}
+	}
+
+	/// Tags `kind` with a fresh request id, then [`Self::submit`]s it.
+	pub fn call(&self, kind: RequestKind) -> EResult<Response> {
+		let id = self.next_id.fetch_add(1, Relaxed);
+		self.submit(Request { id, kind })
+	}
+
+	/// Called by the daemon-facing side (the scheme's char device) to pop the next request to
+	/// hand to the daemon, if any.
+	pub fn poll_request(&self) -> Option<Request> {
+		let mut pending = self.pending.lock();
+		if pending.is_empty() {
+			None
+		} else {
+			Some(pending.remove(0))
+		}
+	}
+
+	/// Called by the daemon-facing side once the daemon has written back its answer to a
+	/// request, waking up the thread blocked in [`Self::submit`].
+	pub fn complete(&self, id: u64, resp: Response) -> EResult<()> {
+		self.completed.lock().insert(id, resp)?;
+		Ok(())
+	}
+}
@@ -17,19 +17,43 @@
  */
 
 //! Filesystem node cache, allowing to handle hard links pointing to the same node.
+//!
+//! [`Node`] also anchors an inotify-style change notification subsystem: [`Node::add_watch`]
+//! registers a watch requesting a mask of [`EventMask`] events onto a process's own
+//! [`EventQueue`], and [`Node::notify`] delivers matching events to every watch registered on the
+//! node. Because [`USED_NODES`] already deduplicates hard links down to one cached `Node`, a
+//! single watch naturally fires for every link to it. [`try_remove`] notifies
+//! [`EventMask::DELETE`] once it actually removes a node from the cache, and [`Node::release`]
+//! notifies [`EventMask::DELETE_SELF`] when it is about to drop the node's last reference, before
+//! removing the node from the cache. [`EventMask`] and [`EventQueue`] themselves live in
+//! [`crate::process::inotify`] rather
+//! than here; see that module's own doc comment for why.
+//!
+//! What this does *not* do: notify on every mutating path. `syscall::fchmod` (the one example this
+//! was added against) calls [`Node::notify`] with [`EventMask::ATTRIB`] directly; `vfs::set_stat`
+//! (the other attrib call site) and every `FileOps::write` impl have no way to reach the `Node`
+//! that owns them from the signatures visible in this tree (`write` only ever receives `&File`,
+//! and no impl in this snapshot derives a `Node` back out of one), so `MODIFY` delivery from a
+//! generic write path is not wired up here.
+//!
+//! [`Node::pages`] additionally deduplicates identical page content across every cached `Node` by
+//! content hash; see [`super::page_cache`] for that subsystem and the gaps in wiring it into the
+//! actual page-fault path.
 
+use super::page_cache::CachedPage;
 use crate::{
 	file::{
 		fs::{FileOps, Filesystem, NodeOps},
 		vfs::mountpoint::MountPoint,
 		FileLocation, FileType, INode,
 	},
-	memory::buddy::PageState,
+	process::inotify::{Event, EventMask, EventQueue},
 	sync::mutex::Mutex,
 };
 use core::{
 	borrow::Borrow,
 	hash::{Hash, Hasher},
+	sync::atomic::{AtomicU32, Ordering::Relaxed},
 };
 use utils::{
 	boxed::Box,
@@ -38,6 +62,21 @@ use utils::{
 	ptr::arc::Arc,
 };
 
+/// A single watch registered on a [`Node`], requesting delivery of events matching `mask` onto
+/// `queue`.
+#[derive(Debug)]
+struct Watch {
+	/// The watch descriptor identifying this watch to the owning process.
+	wd: u32,
+	/// The events this watch requests.
+	mask: EventMask,
+	/// The owning process's event queue.
+	queue: Arc<EventQueue>,
+}
+
+/// Monotonic counter handing out watch descriptors across every [`Node`].
+static NEXT_WD: AtomicU32 = AtomicU32::new(1);
+
 /// A filesystem node, cached by the VFS.
 #[derive(Debug)]
 pub struct Node {
@@ -50,8 +89,11 @@ pub struct Node {
 	/// Handle for open file operations
 	pub file_ops: Box<dyn FileOps>,
 	// TODO need a sparse array, inside of a rwlock
-	/// Mapped pages
-	pub pages: Mutex<Vec<&'static PageState>>,
+	/// Mapped pages, deduplicated by content hash across nodes; see [`super::page_cache`].
+	pub pages: Mutex<Vec<CachedPage>>,
+	/// Watches registered on this node, notified on matching events. A single watch naturally
+	/// fires for every hard link to this node, since they all share this one cached `Node`.
+	pub(super) watches: Mutex<Vec<Watch>>,
 }
 
 impl Node {
@@ -60,6 +102,33 @@ impl Node {
 		&*self.mp.fs
 	}
 
+	/// Registers a new watch requesting `mask`, delivering matching events onto `queue`.
+	///
+	/// Returns the watch descriptor identifying this watch, unique across every node.
+	pub fn add_watch(&self, mask: EventMask, queue: Arc<EventQueue>) -> EResult<u32> {
+		let wd = NEXT_WD.fetch_add(1, Relaxed);
+		self.watches.lock().push(Watch { wd, mask, queue })?;
+		Ok(wd)
+	}
+
+	/// Delivers `mask` to every watch registered on this node whose own mask requests at least one
+	/// of its events.
+	pub fn notify(&self, mask: EventMask) {
+		for watch in self.watches.lock().iter() {
+			if watch.mask.contains(mask) {
+				// Best-effort: a watcher whose queue is full misses the event rather than stalling
+				// the writer that triggered it.
+				let _ = watch.queue.push(Event { wd: watch.wd, mask });
+			}
+		}
+	}
+
+	/// Shorthand for `self.notify(EventMask::ATTRIB)`, so a call site needing only this one event
+	/// (e.g. `syscall::fchmod`) does not need to name [`EventMask`] itself.
+	pub fn notify_attrib(&self) {
+		self.notify(EventMask::ATTRIB)
+	}
+
 	/// Releases the node, removing it from the disk if this is the last reference to it.
 	pub fn release(this: Arc<Self>) -> EResult<()> {
 		// Lock to avoid race condition later
@@ -68,10 +137,14 @@ impl Node {
 		if Arc::strong_count(&this) > 2 {
 			return Ok(());
 		}
+		// The node is leaving the cache: deliver the final event before anything else can observe
+		// it gone.
+		this.notify(EventMask::DELETE_SELF);
 		used_nodes.remove(&this.location);
 		let Some(node) = Arc::into_inner(this) else {
 			return Ok(());
 		};
+		node.release_pages();
 		Self::try_remove(&node.location, &*node.node_ops)
 	}
 
@@ -143,14 +216,23 @@ pub(super) fn insert(node: Arc<Node>) -> AllocResult<()> {
 /// - `ops` is the handle to perform operations on the node
 pub(super) fn try_remove(loc: &FileLocation, ops: &dyn NodeOps) -> EResult<()> {
 	let mut used_nodes = USED_NODES.lock();
-	// Remove from cache
-	if let Some(NodeEntry(node)) = used_nodes.get(loc) {
+	// Remove from cache, keeping a handle to notify its watches once the disk removal below
+	// actually goes through
+	let mut node = None;
+	if let Some(NodeEntry(n)) = used_nodes.get(loc) {
 		// If the node is referenced elsewhere, stop
-		if Arc::strong_count(node) > 1 {
+		if Arc::strong_count(n) > 1 {
 			return Ok(());
 		}
+		node = Some(n.clone());
 		used_nodes.remove(loc);
 	}
+	drop(used_nodes);
 	// Remove the node
-	Node::try_remove(loc, ops)
+	Node::try_remove(loc, ops)?;
+	if let Some(node) = node {
+		node.release_pages();
+		node.notify(EventMask::DELETE);
+	}
+	Ok(())
 }
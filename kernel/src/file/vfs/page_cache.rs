@@ -0,0 +1,220 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Content-hash deduplication of read-only file pages cached on [`super::node::Node`]s.
+//!
+//! The idea, borrowed from redo-style build tools: identical file content should only occupy one
+//! physical page no matter how many [`Node`]s (or processes mapping them) read it. [`hash_page`]
+//! stamps a page's content with a cheap hash the first time it is faulted in; [`acquire`] looks
+//! the hash up in [`INDEX`], compares the looked-up entry's actual content against the caller's to
+//! rule out a hash collision, and hands back the existing [`PageState`] (incrementing its share
+//! count) on a genuine hit, or registers the caller's freshly-read one on a miss. FNV-1a has no
+//! collision resistance against attacker-chosen bytes, so the content comparison — not the hash
+//! match alone — is what decides whether two pages are actually the same content; a hash
+//! collision between different content is treated as a miss, just one that cannot be registered
+//! under the colliding hash (see [`acquire`]). [`release`] decrements the share count and drops
+//! the entry once it reaches zero.
+//!
+//! This module only tracks *that* a page is shared and by how many referents; it does not itself
+//! allocate, map, or free a [`PageState`] — that type has no confirmed methods anywhere in this
+//! tree's snapshot beyond being an opaque `&'static` handle (see [`super::node`]'s own `pages`
+//! field, the only other place it is named), so this module never dereferences one. Consequently a
+//! dedup *hit* in [`Node::fault_page`] has nowhere to return its own freshly-read (and now
+//! redundant) frame: that frame leaks rather than being freed, pending whatever module ends up
+//! owning real frame deallocation.
+//!
+//! The actual fault path that would call [`acquire`]/[`release`] is `MemSpace::handle_page_fault`
+//! → `MemMapping::map`, whose module (`process::mem_space::mapping`) has no file in this tree's
+//! snapshot to wire the call into; the COW-break-on-write half of this request (reallocate and
+//! copy once a write fault hits a page whose share count is still above one) belongs in that same
+//! missing file, for the same reason. [`Node::fault_page`]/[`Node::release_pages`] are written in
+//! the shape that integration would call.
+//!
+//! [`Node::pages`] is a flat `Vec` rather than a true sparse array indexed by page offset (a gap
+//! [`super::node`]'s own `TODO` on that field already flags); [`Node::fault_page`] inherits that
+//! and finds a node's already-cached page by linear scan instead of solving it here.
+
+use super::node::Node;
+use crate::memory::buddy::PageState;
+use crate::sync::mutex::Mutex;
+use utils::{collections::hashmap::HashMap, errno::EResult};
+
+/// A page cached on a [`Node`]: the offset it backs, its content hash, and the (possibly shared)
+/// backing frame.
+#[derive(Debug, Clone, Copy)]
+pub struct CachedPage {
+	/// The page's offset within the node's content, in page-sized units.
+	pub offset: usize,
+	/// The page's content hash, as computed by [`hash_page`] on first read.
+	pub hash: u64,
+	/// The backing frame, possibly shared with other nodes whose content hashed the same.
+	pub page: &'static PageState,
+	/// Whether `hash` is actually registered in [`INDEX`] on this node's behalf.
+	///
+	/// False only for the rare case where `hash` collided with another node's already-cached, but
+	/// different, content: [`acquire`] then hands back this node's own unshared page rather than
+	/// risk returning the other node's bytes, and [`Node::release_pages`] must not call
+	/// [`release`] for an entry this node was never counted in.
+	shared: bool,
+}
+
+/// An entry in [`INDEX`]: a frame shared by `refs` distinct [`Node`]s.
+struct Entry {
+	page: &'static PageState,
+	/// The content that hashed to this entry's key, kept so a hash collision can be told apart
+	/// from a genuine match instead of silently handing back another file's frame (FNV-1a has no
+	/// collision resistance against attacker-chosen bytes).
+	content: &'static [u8],
+	refs: usize,
+}
+
+/// Frames currently shared across at least one [`Node`], keyed by content hash.
+static INDEX: Mutex<HashMap<u64, Entry>> = Mutex::new(HashMap::new());
+
+/// Computes a page's content hash, stable across identical content regardless of which file it
+/// came from.
+///
+/// FNV-1a: cheap enough to run on every first read, and no dependency on a hashing crate this
+/// `no_std` tree does not have.
+pub fn hash_page(content: &[u8]) -> u64 {
+	const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+	const PRIME: u64 = 0x100000001b3;
+	content.iter().fold(OFFSET_BASIS, |hash, &byte| {
+		(hash ^ byte as u64).wrapping_mul(PRIME)
+	})
+}
+
+/// Looks `hash` up in [`INDEX`]. On a hit whose recorded content actually matches `content`,
+/// increments the entry's share count and returns its frame (discarding `page`; see this module's
+/// own doc comment on the resulting leak) alongside `true`. On a hash collision (same `hash`,
+/// different `content`), registers nothing and returns `page` back alongside `false`, so the
+/// caller knows not to treat it as shared. On a genuine miss, registers `page` with a share count
+/// of one and returns it alongside `true`.
+fn acquire(
+	hash: u64,
+	page: &'static PageState,
+	content: &'static [u8],
+) -> EResult<(&'static PageState, bool)> {
+	let mut index = INDEX.lock();
+	if let Some(entry) = index.get_mut(&hash) {
+		if entry.content == content {
+			entry.refs += 1;
+			return Ok((entry.page, true));
+		}
+		// Hash collision: dedup is keyed solely on the hash, so there is no slot to register this
+		// page's content under without evicting the entry already shared by another `Node`. Hand
+		// the caller its own page back unregistered rather than risk returning someone else's
+		// content.
+		return Ok((page, false));
+	}
+	index.insert(hash, Entry { page, content, refs: 1 })?;
+	Ok((page, true))
+}
+
+/// Decrements `hash`'s share count, dropping the entry once no [`Node`] references it anymore.
+/// Returns `true` if the entry was dropped.
+pub fn release(hash: u64) -> bool {
+	let mut index = INDEX.lock();
+	let Some(entry) = index.get_mut(&hash) else {
+		return false;
+	};
+	entry.refs -= 1;
+	if entry.refs == 0 {
+		index.remove(&hash);
+		true
+	} else {
+		false
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	// `acquire` itself (the function 5e48102 actually fixed, comparing `entry.content` against
+	// `content` on a hash hit to tell a genuine match from an FNV-1a collision) cannot be
+	// exercised directly here: its signature requires a `&'static PageState`, and `PageState`
+	// (`crate::memory::buddy::PageState`) has no defining file anywhere in this tree's snapshot
+	// (`memory/buddy.rs` does not exist) — only this module's own `use` and call sites name it.
+	// There is no value of that type this test module can construct, not even a dummy one, since
+	// its layout is unknown. What follows instead covers [`hash_page`], the half of the fix that
+	// is pure and fully testable: `acquire`'s content-comparison branch is exercised the moment a
+	// real `PageState` exists to drive it through, not before.
+
+	#[test_case]
+	fn hash_page_is_deterministic() {
+		assert_eq!(hash_page(b"hello world"), hash_page(b"hello world"));
+	}
+
+	#[test_case]
+	fn hash_page_differs_for_different_content() {
+		assert_ne!(hash_page(b"hello world"), hash_page(b"hello there"));
+	}
+
+	#[test_case]
+	fn hash_page_differs_for_a_length_change_alone() {
+		assert_ne!(hash_page(b"aaaa"), hash_page(b"aaaaa"));
+	}
+
+	#[test_case]
+	fn hash_page_of_empty_content_is_the_fnv_offset_basis() {
+		assert_eq!(hash_page(b""), 0xcbf29ce484222325);
+	}
+}
+
+impl Node {
+	/// Faults in the page at `offset`, deduplicating its content against every other `Node`'s
+	/// cached pages.
+	///
+	/// `read` is called to actually read the page in; its content is hashed and looked up in
+	/// [`INDEX`] before being recorded in [`Self::pages`]. Locks [`Self::pages`] for the whole
+	/// operation, so a concurrent [`Self::release_pages`] on the same node cannot observe a
+	/// half-updated list.
+	pub fn fault_page(
+		&self,
+		offset: usize,
+		read: impl FnOnce() -> EResult<(&'static PageState, &'static [u8])>,
+	) -> EResult<&'static PageState> {
+		let mut pages = self.pages.lock();
+		if let Some(cached) = pages.iter().find(|c| c.offset == offset) {
+			return Ok(cached.page);
+		}
+		let (page, content) = read()?;
+		let hash = hash_page(content);
+		let (page, shared) = acquire(hash, page, content)?;
+		pages.push(CachedPage {
+			offset,
+			hash,
+			page,
+			shared,
+		})?;
+		Ok(page)
+	}
+
+	/// Releases every page this node has cached, decrementing their share counts in [`INDEX`].
+	///
+	/// Called from [`Self::release`]/`try_remove` once the node itself is leaving the cache.
+	pub fn release_pages(&self) {
+		let pages = core::mem::take(&mut *self.pages.lock());
+		for cached in pages {
+			if cached.shared {
+				release(cached.hash);
+			}
+		}
+	}
+}
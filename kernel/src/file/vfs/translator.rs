@@ -0,0 +1,196 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Userspace filesystem translators: a single [`Node`] whose operations are forwarded to a
+//! registered userspace server process instead of being handled by an in-kernel driver, Hurd/GNU
+//! Mach style. This is [`super::super::fs::userfs`]'s own request/response coupling, reused at the
+//! granularity of one node instead of a whole mounted filesystem, so a network or synthetic
+//! filesystem (FTP, HTTP, an archive mount) can live entirely in userspace without a dedicated
+//! in-kernel driver for it.
+//!
+//! [`attach`] builds a [`Node`] whose [`NodeOps`]/[`FileOps`] handles are [`TranslatorNodeOps`]/
+//! [`TranslatorFileOps`], both forwarding over a fresh [`RequestQueue`], and inserts it into the
+//! vfs cache via [`super::node::insert`] so every subsequent lookup of that node resolves to the
+//! translator. [`detach`] reverses that by dropping the kernel's own reference through
+//! [`Node::release`], which tears the node out of the cache (and, from there, would tear the
+//! channel down) once no other reference to it remains.
+//!
+//! This module is a sibling of [`super::node`] rather than a child of it specifically so it can
+//! reach [`super::node`]'s `pub(super)` cache functions; wiring it into the tree only needs a `mod
+//! translator;` line in `file::vfs`'s own root, which has no file in this snapshot to add it to
+//! (the same class of gap [`crate::process::unveil`]'s own doc comment documents for its own
+//! `file::vfs` call site). [`TranslatorNodeOps`] only implements the two [`NodeOps`] methods this
+//! tree confirms by call site (`get_stat`, `remove_node`, both used from
+//! [`super::node::try_remove`]): the trait's full method surface has no file in this snapshot to
+//! check against, so directory enumeration is exposed as a plain inherent method
+//! ([`TranslatorNodeOps::read_dir`]) in the same request/response shape the real trait method
+//! would use, ready to be folded into the `impl NodeOps` block once that method's real name is
+//! known.
+
+use super::node::{self, Node};
+use crate::{
+	file::{
+		fs::{
+			userfs::{
+				proto::{RequestKind, Response},
+				queue::RequestQueue,
+			},
+			FileOps, NodeOps,
+		},
+		vfs::mountpoint::MountPoint,
+		File, FileLocation, FileType, INode, Stat,
+	},
+	sync::mutex::Mutex,
+};
+use utils::{
+	boxed::Box,
+	collections::vec::Vec,
+	errno,
+	errno::{CollectResult, EResult},
+	ptr::arc::Arc,
+};
+
+/// A translator's handle on a [`Node`]'s metadata, forwarding the operations this tree confirms
+/// [`NodeOps`] requires to the daemon over a [`RequestQueue`].
+#[derive(Debug)]
+pub struct TranslatorNodeOps {
+	/// The channel to the registered server process.
+	queue: Arc<RequestQueue>,
+	/// The node's inode, as known to the daemon.
+	inode: INode,
+}
+
+impl NodeOps for TranslatorNodeOps {
+	fn get_stat(&self, _loc: &FileLocation) -> EResult<Stat> {
+		match self.queue.call(RequestKind::GetAttr { inode: self.inode })? {
+			Response::GetAttr(stat) => Ok(stat),
+			Response::Error(e) => Err(e),
+			_ => Err(errno!(EIO)),
+		}
+	}
+
+	fn remove_node(&self, _loc: &FileLocation) -> EResult<()> {
+		match self.queue.call(RequestKind::Remove { inode: self.inode })? {
+			Response::Removed => Ok(()),
+			Response::Error(e) => Err(e),
+			_ => Err(errno!(EIO)),
+		}
+	}
+}
+
+impl TranslatorNodeOps {
+	/// Lists the directory's entries starting after `off`, forwarding the request to the daemon.
+	///
+	/// Not part of the `NodeOps` impl above; see this module's own doc comment for why.
+	pub fn read_dir(&self, off: u64) -> EResult<Vec<(Vec<u8>, INode, FileType)>> {
+		match self.queue.call(RequestKind::ReadDir {
+			inode: self.inode,
+			off,
+		})? {
+			Response::Entries(entries) => Ok(entries),
+			Response::Error(e) => Err(e),
+			_ => Err(errno!(EIO)),
+		}
+	}
+}
+
+/// A translator's handle on a [`Node`]'s content, forwarding `read`/`write` to the daemon over a
+/// [`RequestQueue`].
+#[derive(Debug)]
+pub struct TranslatorFileOps {
+	/// The channel to the registered server process.
+	queue: Arc<RequestQueue>,
+	/// The node's inode, as known to the daemon.
+	inode: INode,
+}
+
+impl FileOps for TranslatorFileOps {
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		match self.queue.call(RequestKind::Read {
+			inode: self.inode,
+			off,
+			len: buf.len(),
+		})? {
+			Response::Read(data) => {
+				let len = data.len().min(buf.len());
+				buf[..len].copy_from_slice(&data[..len]);
+				Ok(len)
+			}
+			Response::Error(e) => Err(e),
+			_ => Err(errno!(EIO)),
+		}
+	}
+
+	fn write(&self, _file: &File, off: u64, buf: &[u8]) -> EResult<usize> {
+		let data = buf.iter().copied().collect::<CollectResult<Vec<_>>>().0?;
+		match self.queue.call(RequestKind::Write {
+			inode: self.inode,
+			off,
+			data,
+		})? {
+			Response::Write(len) => Ok(len),
+			Response::Error(e) => Err(e),
+			_ => Err(errno!(EIO)),
+		}
+	}
+}
+
+/// Attaches a translator to `inode` on `mp`: builds a [`Node`] backed by [`TranslatorNodeOps`]/
+/// [`TranslatorFileOps`] over a fresh [`RequestQueue`], inserts it into the vfs cache via
+/// [`node::insert`], and returns the queue so the caller can hand its daemon-facing end to the
+/// registered server process.
+pub fn attach(inode: INode, mp: Arc<MountPoint>) -> EResult<Arc<RequestQueue>> {
+	let queue = Arc::new(RequestQueue::new())?;
+	let translator_node = Arc::new(Node {
+		inode,
+		mp,
+		node_ops: Box::new(TranslatorNodeOps {
+			queue: queue.clone(),
+			inode,
+		})?,
+		file_ops: Box::new(TranslatorFileOps {
+			queue: queue.clone(),
+			inode,
+		})?,
+		pages: Mutex::new(Vec::new()),
+		watches: Mutex::new(Vec::new()),
+	})?;
+	node::insert(translator_node)?;
+	Ok(queue)
+}
+
+/// Detaches a previously-attached translator, dropping the kernel's own reference to `node` so
+/// [`Node::release`] tears it (and, from there, its channel) down once no other reference remains.
+pub fn detach(node: Arc<Node>) -> EResult<()> {
+	Node::release(node)
+}
+
+/// Channels handed out by [`register_queue`], indexed by the opaque handle returned to userspace.
+///
+/// A real fd would let the daemon `read`/`write` its channel directly; that needs `file::fd`'s
+/// `FileDescriptorTable` machinery, which has no file in this snapshot (the same gap
+/// [`crate::process::pidfd`]'s own doc comment documents), so this table is the closest available
+/// substitute in the meantime.
+static QUEUES: Mutex<Vec<Option<Arc<RequestQueue>>>> = Mutex::new(Vec::new());
+
+/// Registers `queue` and returns the opaque handle identifying it, for [`QUEUES`].
+pub fn register_queue(queue: Arc<RequestQueue>) -> EResult<usize> {
+	let mut queues = QUEUES.lock();
+	queues.push(Some(queue))?;
+	Ok(queues.len() - 1)
+}
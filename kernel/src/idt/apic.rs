@@ -0,0 +1,122 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Local APIC / I/O APIC support.
+//!
+//! Unlike the legacy 8259 [`super::pic`], the APIC lets external interrupts be routed to an
+//! arbitrary vector on an arbitrary CPU, and is required to start application processors. This
+//! module detects the Local APIC, maps its MMIO page and enables it. It does not program the I/O
+//! APIC's redirection table or start application processors: external interrupts are still
+//! routed through the 8259.
+
+use crate::memory::{
+	mmio,
+	mmio::{MmioAttrs, MmioRegion},
+	PhysAddr,
+};
+use core::arch::asm;
+
+/// The MSR holding the Local APIC's base physical address and enable bit.
+const IA32_APIC_BASE_MSR: u32 = 0x1b;
+/// Bit of [`IA32_APIC_BASE_MSR`] enabling the Local APIC.
+const APIC_BASE_ENABLE: u64 = 1 << 11;
+
+/// Local APIC register offset: End Of Interrupt.
+const LAPIC_REG_EOI: usize = 0xb0;
+/// Local APIC register offset: Spurious Interrupt Vector.
+const LAPIC_REG_SPURIOUS: usize = 0xf0;
+/// Bit of [`LAPIC_REG_SPURIOUS`] enabling the Local APIC's interrupt delivery.
+const LAPIC_SOFTWARE_ENABLE: u32 = 1 << 8;
+
+/// Reads the Model-Specific Register `msr`.
+fn rdmsr(msr: u32) -> u64 {
+	let (high, low): (u32, u32);
+	unsafe {
+		asm!("rdmsr", in("ecx") msr, out("eax") low, out("edx") high);
+	}
+	((high as u64) << 32) | low as u64
+}
+
+/// Writes `value` to the Model-Specific Register `msr`.
+fn wrmsr(msr: u32, value: u64) {
+	let low = value as u32;
+	let high = (value >> 32) as u32;
+	unsafe {
+		asm!("wrmsr", in("ecx") msr, in("eax") low, in("edx") high);
+	}
+}
+
+/// Tells whether the current CPU supports the Local APIC, by checking CPUID leaf 1, EDX bit 9.
+pub fn is_supported() -> bool {
+	let edx: u32;
+	unsafe {
+		asm!(
+			"push ebx",
+			"cpuid",
+			"pop ebx",
+			in("eax") 1,
+			lateout("eax") _,
+			lateout("ecx") _,
+			lateout("edx") edx,
+		);
+	}
+	edx & (1 << 9) != 0
+}
+
+/// Handle to the Local APIC, mapped once at [`init`] time.
+pub struct LocalApic {
+	/// The Local APIC's MMIO page.
+	region: MmioRegion,
+}
+
+impl LocalApic {
+	/// Signals the end of interrupt servicing to the Local APIC.
+	pub fn eoi(&self) {
+		self.region.write32(LAPIC_REG_EOI, 0);
+	}
+}
+
+/// Detects and enables the Local APIC, mapping its MMIO page into kernel space via
+/// [`mmio::ioremap`].
+///
+/// Returns `None` if the current CPU has no Local APIC.
+pub fn init() -> Option<LocalApic> {
+	if !is_supported() {
+		return None;
+	}
+	let base = rdmsr(IA32_APIC_BASE_MSR);
+	let phys = PhysAddr((base & 0xffff_f000) as _);
+	wrmsr(IA32_APIC_BASE_MSR, base | APIC_BASE_ENABLE);
+	let region = mmio::ioremap(phys, core::mem::size_of::<u32>() * 64, MmioAttrs::DEVICE).ok()?;
+	let lapic = LocalApic { region };
+	// Enable interrupt delivery, with the spurious vector set to the top of the usable range.
+	lapic.region.write32(
+		LAPIC_REG_SPURIOUS,
+		lapic.region.read32(LAPIC_REG_SPURIOUS) | LAPIC_SOFTWARE_ENABLE | 0xff,
+	);
+	Some(lapic)
+}
+
+// TODO (SMP): program the I/O APIC's redirection table so each external IRQ can target an
+// arbitrary vector/CPU, replacing the 8259's fixed routing; mask the 8259 once this is done so
+// both controllers don't race to deliver the same line.
+//
+// TODO (SMP): send the INIT/SIPI sequence to each application processor listed in the ACPI MADT,
+// handing it a private IDT and stack, then register its Local APIC timer as that core's
+// scheduling tick. This requires a real-mode AP trampoline and a per-CPU `Scheduler` instance,
+// neither of which exist yet in this tree.
@@ -0,0 +1,155 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Dynamic registration of IRQ handlers, decoupling device drivers from the IDT's static vector
+//! table.
+//!
+//! A driver claims its line with [`register_handler`] instead of editing [`super`]'s `init`
+//! function. Lines can be shared: every handler registered on a vector is called, in
+//! registration order, until one of them reports the interrupt as handled.
+
+use super::ENTRIES_COUNT;
+use utils::{collections::vec::Vec, errno::EResult, lock::Mutex, ptr::arc::Arc};
+
+/// Flags passed at registration time.
+pub type HandlerFlags = u8;
+/// The line may be shared with other handlers.
+pub const IRQF_SHARED: HandlerFlags = 0b1;
+
+/// Whether an [`InterruptHandler`] serviced the interrupt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IrqReturn {
+	/// The interrupt was not meant for this handler.
+	None,
+	/// The interrupt was serviced.
+	Handled,
+}
+
+/// A driver-provided callback invoked when its IRQ line fires.
+pub trait InterruptHandler {
+	/// Services the interrupt, returning whether it was meant for this handler.
+	fn handle(&self) -> IrqReturn;
+}
+
+/// An interrupt controller, abstracting over the legacy 8259 [`super::pic`] and, eventually,
+/// the Local/IO APIC.
+///
+/// This mirrors the enable/priority/claim/EOI model of a GIC: each vector can be masked
+/// independently, given a priority, and must be acknowledged once serviced.
+pub trait IrqChip: Sync {
+	/// Masks (disables) the given IRQ line.
+	fn mask(&self, irq: u8);
+	/// Unmasks (enables) the given IRQ line.
+	fn unmask(&self, irq: u8);
+	/// Signals the end of interrupt servicing for `irq`, allowing the controller to deliver the
+	/// next one.
+	fn eoi(&self, irq: u8);
+	/// Sets the priority of `irq`, if the controller supports it.
+	///
+	/// The default implementation is a no-op, since the 8259 PIC has no notion of priority
+	/// beyond the fixed cascade order.
+	fn set_priority(&self, _irq: u8, _priority: u8) {}
+}
+
+/// The legacy 8259 PIC, implementing [`IrqChip`] on top of [`super::pic`]'s existing
+/// mask/EOI primitives.
+pub struct Pic;
+
+impl IrqChip for Pic {
+	fn mask(&self, irq: u8) {
+		super::pic::mask(irq);
+	}
+
+	fn unmask(&self, irq: u8) {
+		super::pic::unmask(irq);
+	}
+
+	fn eoi(&self, irq: u8) {
+		super::pic::end_of_interrupt(irq);
+	}
+}
+
+/// The currently active interrupt controller.
+static CHIP: Pic = Pic;
+
+/// Returns the currently active [`IrqChip`].
+pub fn chip() -> &'static dyn IrqChip {
+	&CHIP
+}
+
+/// A handler registered on a given vector, along with the flags it was registered with.
+struct Entry {
+	handler: Arc<dyn InterruptHandler>,
+	flags: HandlerFlags,
+}
+
+/// Per-line list of registered handlers, indexed by IRQ line number (the IDT vector minus the
+/// PIC's `0x20` offset).
+static HANDLERS: Mutex<[Vec<Entry>; ENTRIES_COUNT]> =
+	Mutex::new([const { Vec::new() }; ENTRIES_COUNT]);
+
+/// Registers `handler` on `irq`, with the given `flags`.
+///
+/// If the line is already claimed by another handler, the function returns
+/// [`utils::errno::Errno::EBUSY`] unless this registration and every existing one set
+/// [`IRQF_SHARED`].
+pub fn register_handler(
+	irq: u8,
+	handler: Arc<dyn InterruptHandler>,
+	flags: HandlerFlags,
+) -> EResult<()> {
+	let mut handlers = HANDLERS.lock();
+	let list = &mut handlers[irq as usize];
+	if !list.is_empty() && (flags & IRQF_SHARED == 0 || list.iter().any(|e| e.flags & IRQF_SHARED == 0))
+	{
+		return Err(utils::errno!(EBUSY));
+	}
+	list.push(Entry { handler, flags })?;
+	chip().unmask(irq);
+	Ok(())
+}
+
+/// Removes every handler on `irq` pointing to the same instance as `handler`.
+///
+/// If the line ends up without any handler left, it is masked.
+pub fn free_handler(irq: u8, handler: &Arc<dyn InterruptHandler>) {
+	let mut handlers = HANDLERS.lock();
+	let list = &mut handlers[irq as usize];
+	list.retain(|e| !Arc::ptr_eq(&e.handler, handler));
+	if list.is_empty() {
+		chip().mask(irq);
+	}
+}
+
+/// Dispatches `irq` to every handler registered on it, in order, stopping as soon as one reports
+/// [`IrqReturn::Handled`], then acknowledges the interrupt with the active [`IrqChip`].
+///
+/// Called by `super::event_handler` for every vector in the IRQ range, so a handler registered
+/// with [`register_handler`] — including [`crate::device::rtc::RtcHandler`]'s periodic tick —
+/// actually runs once its line fires.
+pub fn dispatch(irq: u8) {
+	{
+		let handlers = HANDLERS.lock();
+		for entry in &handlers[irq as usize] {
+			if entry.handler.handle() == IrqReturn::Handled {
+				break;
+			}
+		}
+	}
+	chip().eoi(irq);
+}
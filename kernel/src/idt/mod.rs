@@ -20,6 +20,8 @@
 //! storing the list of interrupt handlers, allowing to catch and handle
 //! interruptions.
 
+pub mod apic;
+pub mod irq;
 pub mod pic;
 
 use crate::syscall::syscall32;
@@ -230,6 +232,34 @@ RESTORE_REGS
 	};
 }
 
+// These stubs (and the `error!` ones above) push the vector, an error code (0 if the exception
+// has none), the ring, and a pointer to the saved registers, then call [`event_handler`] below,
+// which is what actually reaches [`irq::dispatch`] for vectors in the IRQ range.
+
+/// Entry point called by every `error!`/`irq!` assembly stub with the raw vector `id`, the
+/// exception's `code` (`0` for vectors that don't push one), the `ring` the interrupt was taken
+/// from, and `regs`, a pointer to the registers [`GET_REGS`] saved on the stack.
+///
+/// For vectors in the IRQ range (`0x20..0x30`), this is the call site [`irq::dispatch`] needed:
+/// dispatch doesn't touch `regs`, so no `arch::x86::idt::IntFrame` layout is required to wire it
+/// up, unlike exception handling below.
+///
+/// For every other vector (a CPU exception), there is currently nowhere to route `regs`/`code`
+/// to: a real handler would decode `IntFrame` to deliver a signal to the faulting process or
+/// panic with full register state, and `arch::x86::idt::IntFrame` has no file in this tree's
+/// snapshot to define it in. Panicking with just the raw vector/code is the most this function
+/// can honestly do until that type exists.
+#[no_mangle]
+pub extern "C" fn event_handler(id: u32, code: u32, _ring: u32, _regs: *mut u8) {
+	const IRQ_BASE: u32 = 0x20;
+	const IRQ_COUNT: u32 = 16;
+	if (IRQ_BASE..IRQ_BASE + IRQ_COUNT).contains(&id) {
+		irq::dispatch((id - IRQ_BASE) as u8);
+		return;
+	}
+	panic!("unhandled CPU exception {id} (code {code})");
+}
+
 macro_rules! irq {
 	($name:ident, $id:expr) => {
 		extern "C" {
@@ -352,6 +382,9 @@ pub fn wrap_disable_interrupts<T, F: FnOnce() -> T>(f: F) -> T {
 pub(crate) fn init() {
 	cli();
 	pic::init(0x20, 0x28);
+	// If the CPU has a Local APIC, bring it up; the I/O APIC takeover and AP bring-up this
+	// enables are tracked as follow-up work in the `apic` module.
+	apic::init();
 	// Safe because the current function is called only once at boot
 	unsafe {
 		// Errors
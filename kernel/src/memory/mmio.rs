@@ -0,0 +1,172 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Mapping of device MMIO regions, kept separate from the identity-mapped RAM so each region can
+//! carry its own cache/memory attributes.
+//!
+//! [`ioremap`] is the MMIO counterpart of a regular page mapping: instead of the cached,
+//! write-back attributes used for RAM, the caller picks the attribute set appropriate for the
+//! device register block being mapped (typically uncacheable). The returned [`MmioRegion`] unmaps
+//! itself on drop.
+
+use crate::{
+	arch::x86::paging::{FLAG_CACHE_DISABLE, FLAG_PRESENT, FLAG_WRITE, FLAG_WRITE_THROUGH},
+	memory,
+	memory::{vmem::KERNEL_VMEM, PhysAddr, VirtAddr},
+};
+use core::{
+	ptr,
+	sync::atomic::{AtomicUsize, Ordering},
+};
+use utils::{collections::vec::Vec, errno, errno::EResult, lock::Mutex, limits::PAGE_SIZE};
+
+/// Attributes controlling how an [`ioremap`]ped region is cached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MmioAttrs {
+	/// Disables caching entirely, as required for most device registers, where a stale cached
+	/// read/write would be observably wrong.
+	pub cache_disable: bool,
+	/// Uses write-through instead of write-back caching. Ignored when `cache_disable` is set.
+	pub write_through: bool,
+}
+
+impl MmioAttrs {
+	/// The attributes used for ordinary device registers: uncached, as is correct for the vast
+	/// majority of MMIO.
+	pub const DEVICE: Self = Self {
+		cache_disable: true,
+		write_through: false,
+	};
+
+	/// Converts the attribute set to the architecture's page-table flag bits.
+	fn to_page_flags(self) -> usize {
+		let mut flags = FLAG_PRESENT | FLAG_WRITE;
+		if self.cache_disable {
+			flags |= FLAG_CACHE_DISABLE;
+		} else if self.write_through {
+			flags |= FLAG_WRITE_THROUGH;
+		}
+		flags
+	}
+}
+
+/// A record of one currently active MMIO mapping, kept so overlapping requests can be rejected
+/// and so introspection (in the style of `/proc/mounts`) can later enumerate them.
+struct Region {
+	phys: PhysAddr,
+	virt: VirtAddr,
+	len: usize,
+}
+
+/// The reserved window of kernel virtual space `ioremap` bump-allocates from.
+///
+/// Mappings are never reclaimed on [`iounmap`] (only the page-table entries are torn down), which
+/// is acceptable since the number of distinct MMIO regions a driver set maps over the kernel's
+/// lifetime is small and bounded.
+const MMIO_WINDOW_SIZE: usize = 64 * 1024 * 1024;
+/// Byte offset of the end of the MMIO window below `PROCESS_END`, keeping the window clear of
+/// `mem_space`'s reserved `COPY_BUFFER` page so they do not alias.
+const MMIO_WINDOW_END: usize = 3 * PAGE_SIZE;
+
+static NEXT_FREE: AtomicUsize = AtomicUsize::new(0);
+static ACTIVE: Mutex<Vec<Region>> = Mutex::new(Vec::new());
+
+/// Maps `len` bytes of physical MMIO space starting at `phys` into kernel space with the given
+/// `attrs`, returning a guard that unmaps it on drop.
+///
+/// Fails with `EEXIST` if the requested range overlaps an already-active mapping, and `ENOMEM` if
+/// the MMIO window is exhausted.
+pub fn ioremap(phys: PhysAddr, len: usize, attrs: MmioAttrs) -> EResult<MmioRegion> {
+	let len = len.next_multiple_of(PAGE_SIZE);
+	let pages = len / PAGE_SIZE;
+	{
+		let active = ACTIVE.lock();
+		let new_end = phys.0 + len;
+		let overlap = active
+			.iter()
+			.any(|r| phys.0 < r.phys.0 + r.len && r.phys.0 < new_end);
+		if overlap {
+			return Err(errno!(EEXIST));
+		}
+	}
+	let offset = NEXT_FREE.fetch_add(len, Ordering::AcqRel);
+	if offset + len > MMIO_WINDOW_SIZE {
+		return Err(errno!(ENOMEM));
+	}
+	let virt = VirtAddr(memory::PROCESS_END.0 - MMIO_WINDOW_END - MMIO_WINDOW_SIZE + offset);
+	KERNEL_VMEM
+		.get()
+		.lock()
+		.map_range(phys, virt, pages, attrs.to_page_flags());
+	ACTIVE.lock().push(Region { phys, virt, len })?;
+	Ok(MmioRegion { phys, virt, len })
+}
+
+/// Tears down the page-table entries for a region previously returned by [`ioremap`].
+///
+/// Called automatically by [`MmioRegion`]'s `Drop` implementation; driver code should not need to
+/// call this directly.
+fn iounmap(phys: PhysAddr, virt: VirtAddr, len: usize) {
+	ACTIVE.lock().retain(|r| r.phys.0 != phys.0);
+	KERNEL_VMEM
+		.get()
+		.lock()
+		.unmap_range(virt, len / PAGE_SIZE);
+}
+
+/// A live MMIO mapping, dereferencing to the mapped region through volatile accesses.
+///
+/// Unmaps itself when dropped.
+pub struct MmioRegion {
+	phys: PhysAddr,
+	virt: VirtAddr,
+	len: usize,
+}
+
+impl MmioRegion {
+	/// Returns the size of the mapped region, in bytes.
+	pub fn len(&self) -> usize {
+		self.len
+	}
+
+	/// Volatile-reads a `u32` at byte offset `off` within the region.
+	///
+	/// # Panics
+	/// Panics if the 4-byte read would go past the end of the mapped region.
+	pub fn read32(&self, off: usize) -> u32 {
+		assert!(off + 4 <= self.len);
+		unsafe { ptr::with_exposed_provenance::<u32>(self.virt.0 + off).read_volatile() }
+	}
+
+	/// Volatile-writes `val` as a `u32` at byte offset `off` within the region.
+	///
+	/// # Panics
+	/// Panics if the 4-byte write would go past the end of the mapped region.
+	pub fn write32(&self, off: usize, val: u32) {
+		assert!(off + 4 <= self.len);
+		unsafe {
+			ptr::with_exposed_provenance_mut::<u32>(self.virt.0 + off).write_volatile(val);
+		}
+	}
+}
+
+impl Drop for MmioRegion {
+	fn drop(&mut self) {
+		iounmap(self.phys, self.virt, self.len);
+	}
+}
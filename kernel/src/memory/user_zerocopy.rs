@@ -0,0 +1,193 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Zero-copy borrowing of userspace memory, for callers that would otherwise bounce every byte
+//! through a kernel-owned buffer via `copy_from_user_vec`/`copy_to_user`.
+//!
+//! [`UserSlice::borrow`] validates the requested user range the same way the copying helpers do,
+//! then hands back a guard dereferencing straight to the (already mapped) user pages, instead of
+//! allocating and filling a copy. [`UserIoVec::borrow_all`] does the same lazily, one segment at a
+//! time. The guard keeps SMAP disabled
+//! and the range reserved against other mutable borrows until it is dropped.
+
+use crate::{memory::vmem::SmapGuard, process::mem_space::bound_check};
+use core::{
+	marker::PhantomData,
+	ops::{Deref, DerefMut},
+	slice,
+};
+use utils::{collections::vec::Vec, errno, errno::EResult, lock::Mutex};
+
+/// A range of userspace memory currently lent out by [`UserSlice::borrow`].
+struct Borrow {
+	start: usize,
+	end: usize,
+	write: bool,
+}
+
+/// Ranges currently borrowed without copying, checked on every new borrow to refuse overlapping
+/// mutable access.
+static ACTIVE_BORROWS: Mutex<Vec<Borrow>> = Mutex::new(Vec::new());
+
+fn overlaps(a_start: usize, a_end: usize, b_start: usize, b_end: usize) -> bool {
+	a_start < b_end && b_start < a_end
+}
+
+/// A live, zero-copy borrow of a range of userspace memory.
+///
+/// Dropping the guard unpins the range (removing it from [`ACTIVE_BORROWS`]) and restores SMAP
+/// protection.
+pub struct UserSlice<'a> {
+	ptr: *mut u8,
+	len: usize,
+	write: bool,
+	_smap: SmapGuard,
+	_marker: PhantomData<&'a mut [u8]>,
+}
+
+impl<'a> UserSlice<'a> {
+	/// Validates and borrows `len` bytes of userspace memory starting at `ptr`, without copying.
+	///
+	/// If `write` is set, the range must not already be borrowed (mutably or not); if clear, it
+	/// may overlap other read-only borrows but not a concurrent mutable one.
+	pub fn borrow(ptr: usize, len: usize, write: bool) -> EResult<Self> {
+		if !bound_check(ptr, len) {
+			return Err(errno!(EFAULT));
+		}
+		let end = ptr + len;
+		{
+			let mut borrows = ACTIVE_BORROWS.lock();
+			let conflict = borrows
+				.iter()
+				.any(|b| overlaps(ptr, end, b.start, b.end) && (write || b.write));
+			if conflict {
+				return Err(errno!(EBUSY));
+			}
+			borrows.push(Borrow {
+				start: ptr,
+				end,
+				write,
+			})?;
+		}
+		Ok(Self {
+			ptr: ptr as *mut u8,
+			len,
+			write,
+			// Safe: the range has just been validated and reserved above.
+			_smap: unsafe { SmapGuard::new() },
+			_marker: PhantomData,
+		})
+	}
+}
+
+impl Deref for UserSlice<'_> {
+	type Target = [u8];
+
+	fn deref(&self) -> &[u8] {
+		// Safe: `ptr`/`len` were validated by `bound_check` in `borrow`, and the borrow is
+		// reserved in `ACTIVE_BORROWS` for its whole lifetime.
+		unsafe { slice::from_raw_parts(self.ptr, self.len) }
+	}
+}
+
+impl DerefMut for UserSlice<'_> {
+	fn deref_mut(&mut self) -> &mut [u8] {
+		debug_assert!(self.write, "borrowed read-only but accessed mutably");
+		// Safe: see `deref`; `write` was required true when the borrow was reserved.
+		unsafe { slice::from_raw_parts_mut(self.ptr, self.len) }
+	}
+}
+
+impl Drop for UserSlice<'_> {
+	fn drop(&mut self) {
+		let start = self.ptr as usize;
+		let end = start + self.len;
+		// Remove only this borrow's own entry: an identical read-only borrow may still be live
+		let mut borrows = ACTIVE_BORROWS.lock();
+		let pos = borrows
+			.iter()
+			.position(|b| b.start == start && b.end == end && b.write == self.write);
+		if let Some(i) = pos {
+			borrows.remove(i);
+		}
+	}
+}
+
+/// A single segment of a scatter/gather list, as laid out by the C `struct iovec`.
+#[derive(Clone, Copy)]
+pub struct IoVec {
+	pub base: usize,
+	pub len: usize,
+}
+
+/// A resettable, zero-copy iterator over the segments of a `struct iovec` array.
+///
+/// Borrows are produced lazily, one at a time, so a caller that performs a short write can
+/// [`advance`](Self::advance) the cursor by exactly what was consumed and retry, re-running the
+/// access checks on the remainder without re-borrowing what was already consumed, or
+/// [`reset`](Self::reset) the cursor to the beginning of the list.
+pub struct UserIoVec<'a> {
+	segments: &'a [IoVec],
+	/// Index of the segment the cursor is currently in.
+	seg: usize,
+	/// Byte offset within `segments[seg]`.
+	off: usize,
+	write: bool,
+}
+
+impl<'a> UserIoVec<'a> {
+	/// Wraps `segments` for borrowing, without validating or mapping anything yet: validation
+	/// happens lazily, one segment at a time, as [`Self::next_segment`] is called.
+	pub fn borrow_all(segments: &'a [IoVec], write: bool) -> Self {
+		Self {
+			segments,
+			seg: 0,
+			off: 0,
+			write,
+		}
+	}
+
+	/// Borrows and returns the next not-yet-fully-consumed segment, or `None` once every segment
+	/// has been consumed.
+	pub fn next_segment(&mut self) -> EResult<Option<UserSlice<'a>>> {
+		while let Some(seg) = self.segments.get(self.seg) {
+			if self.off >= seg.len {
+				self.seg += 1;
+				self.off = 0;
+				continue;
+			}
+			let slice = UserSlice::borrow(seg.base + self.off, seg.len - self.off, self.write)?;
+			return Ok(Some(slice));
+		}
+		Ok(None)
+	}
+
+	/// Advances the cursor by `n` bytes within the segment last returned by
+	/// [`Self::next_segment`], so that the next call resumes right after what was actually
+	/// consumed.
+	pub fn advance(&mut self, n: usize) {
+		self.off += n;
+	}
+
+	/// Resets the cursor to the very beginning, so the whole scatter/gather list can be retried
+	/// (e.g. after a short write detected downstream).
+	pub fn reset(&mut self) {
+		self.seg = 0;
+		self.off = 0;
+	}
+}
@@ -33,9 +33,56 @@ use crate::{
 	sync::{mutex::Mutex, once::OnceInit},
 	tty::vga,
 };
-use core::{cmp::min, ptr::NonNull};
+use core::{cmp::min, ops::BitOr, ptr::NonNull};
 use utils::limits::PAGE_SIZE;
 
+/// A page permission, independent of the underlying architecture's paging flags.
+///
+/// This mirrors the bit layout of `process::mem_space::{PROT_READ, PROT_WRITE, PROT_EXEC}`, but
+/// is redefined at this layer so `memory` does not need to depend on `process`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Permission(u8);
+
+impl Permission {
+	/// No access.
+	pub const NONE: Self = Self(0b000);
+	/// The page can be read.
+	pub const READ: Self = Self(0b001);
+	/// The page can be written.
+	pub const WRITE: Self = Self(0b010);
+	/// The page can be executed.
+	pub const EXEC: Self = Self(0b100);
+
+	/// Tells whether `self` grants every permission in `other`.
+	pub const fn contains(self, other: Self) -> bool {
+		self.0 & other.0 == other.0
+	}
+
+	/// Tells whether the permission is both writable and executable, which this API refuses to
+	/// map (see [`VMem::map_perm`]).
+	const fn is_write_exec(self) -> bool {
+		self.contains(Self::WRITE) && self.contains(Self::EXEC)
+	}
+}
+
+/// Policy flag named after holey-bytes' `paging::OUT_PROG_EXEC`: once a [`VMem`]'s program image
+/// has been sealed with [`VMem::seal_program_image`], [`VMem::map_perm`] refuses to map an
+/// executable page outside it, the same way [`Permission::is_write_exec`] refuses W^X.
+///
+/// This is a coarser backstop than W^X: a page that is EXEC-only (never simultaneously WRITE) is
+/// still refused here if its address falls outside the range the ELF loader originally claimed,
+/// closing the gap where something maps a fresh executable page post-load instead of writing
+/// into one that was already there.
+pub const OUT_PROG_EXEC: bool = true;
+
+impl BitOr for Permission {
+	type Output = Self;
+
+	fn bitor(self, rhs: Self) -> Self {
+		Self(self.0 | rhs.0)
+	}
+}
+
 /// A virtual memory context.
 ///
 /// This structure implements operations to modify virtual memory in an architecture-independent
@@ -47,6 +94,10 @@ pub struct VMem {
 	/// The root paging object.
 	#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 	table: NonNull<x86::paging::Table>,
+	/// The virtual address range (start inclusive, end exclusive) of the program image sealed
+	/// with [`Self::seal_program_image`], or `None` if this context hasn't sealed one yet (in
+	/// particular, every kernel context never does).
+	prog_image: Option<(VirtAddr, VirtAddr)>,
 }
 
 impl VMem {
@@ -60,9 +111,24 @@ impl VMem {
 		Self {
 			#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 			table: x86::paging::alloc(),
+			prog_image: None,
 		}
 	}
 
+	/// Seals `range` (start inclusive, end exclusive, both page-aligned) as the virtual address
+	/// span of this context's initially loaded program image, turning on [`OUT_PROG_EXEC`]
+	/// enforcement: from this call on, [`Self::map_perm`] panics if asked to map an executable
+	/// page outside `range`, the same debug-assert convention it already uses for W^X.
+	///
+	/// Meant to be called once, right after the ELF loader finishes mapping a program's
+	/// segments; a later call replaces the sealed range rather than extending it, since a
+	/// process loads exactly one initial image. Nothing in this tree's snapshot calls this yet
+	/// (the ELF loader migrating onto [`Self::map_perm`] is tracked separately), but the
+	/// enforcement itself is live for any caller that does.
+	pub fn seal_program_image(&mut self, range: (VirtAddr, VirtAddr)) {
+		self.prog_image = Some(range);
+	}
+
 	/// Returns an immutable reference to the **architecture-dependent** inner representation.
 	#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 	pub fn inner(&self) -> &x86::paging::Table {
@@ -114,6 +180,70 @@ impl VMem {
 		}
 	}
 
+	/// Like [`Self::map`], but takes a portable [`Permission`] instead of raw architecture-
+	/// dependent flags.
+	///
+	/// `user` tells whether the page must be accessible from ring 3; `global` tells whether the
+	/// page's TLB entry should survive a context switch (meaningless, and always `false`, for
+	/// userspace mappings).
+	///
+	/// # Panics
+	///
+	/// Enforces W^X: panics in debug builds if `perm` is both writable and executable. This tree
+	/// has no NX page-table bit to fall back on (that, and enabling `EFER.NXE`, would live in
+	/// `arch::x86::paging`, which this snapshot doesn't include), so a writable+executable page
+	/// cannot be rejected at the hardware level; refusing to express it through this API is the
+	/// hardening this can actually provide.
+	///
+	/// Also enforces [`OUT_PROG_EXEC`] once [`Self::seal_program_image`] has been called: panics
+	/// in debug builds if `perm` is executable and `virtaddr` falls outside the sealed range.
+	#[inline]
+	pub fn map_perm(
+		&mut self,
+		physaddr: PhysAddr,
+		virtaddr: VirtAddr,
+		perm: Permission,
+		user: bool,
+		global: bool,
+	) {
+		debug_assert!(!perm.is_write_exec(), "refusing to map a writable and executable page");
+		if let Some((start, end)) = self.prog_image {
+			let outside_image = virtaddr < start || virtaddr >= end;
+			debug_assert!(
+				!(OUT_PROG_EXEC && perm.contains(Permission::EXEC) && outside_image),
+				"OUT_PROG_EXEC: refusing to map an executable page outside the sealed program image"
+			);
+		}
+		let mut flags = 0;
+		if perm.contains(Permission::WRITE) {
+			flags |= FLAG_WRITE;
+		}
+		if user {
+			flags |= FLAG_USER;
+		}
+		if global {
+			flags |= FLAG_GLOBAL;
+		}
+		self.map(physaddr, virtaddr, flags);
+	}
+
+	/// Like [`Self::map_perm`] but on a range of several pages, mirroring [`Self::map_range`].
+	pub fn map_range_perm(
+		&mut self,
+		physaddr: PhysAddr,
+		virtaddr: VirtAddr,
+		pages: usize,
+		perm: Permission,
+		user: bool,
+		global: bool,
+	) {
+		for i in 0..pages {
+			let physaddr = physaddr + i * PAGE_SIZE;
+			let virtaddr = virtaddr + i * PAGE_SIZE;
+			self.map_perm(physaddr, virtaddr, perm, user, global);
+		}
+	}
+
 	/// Unmaps a single page of virtual memory at `virtaddr`.
 	#[inline]
 	pub fn unmap(&mut self, virtaddr: VirtAddr) {
@@ -226,6 +356,38 @@ pub unsafe fn smap_disable<F: FnOnce() -> T, T>(f: F) -> T {
 	res
 }
 
+/// An RAII guard disabling SMAP for as long as it is held, re-enabling it on drop.
+///
+/// Unlike [`smap_disable`], this does not require the access to be scoped to a single closure,
+/// which is necessary for APIs that hand out a borrowed slice of user memory that outlives the
+/// call that created it.
+///
+/// # Safety
+///
+/// Same requirements as [`smap_disable`]: the caller must not let SMAP-gated accesses escape the
+/// guard's lifetime.
+pub struct SmapGuard(());
+
+impl SmapGuard {
+	/// Disables SMAP and returns a guard that re-enables it once dropped.
+	///
+	/// # Safety
+	///
+	/// Same requirements as [`smap_disable`].
+	pub unsafe fn new() -> Self {
+		x86::set_smap_enabled(false);
+		Self(())
+	}
+}
+
+impl Drop for SmapGuard {
+	fn drop(&mut self) {
+		unsafe {
+			x86::set_smap_enabled(true);
+		}
+	}
+}
+
 /// Executes the given closure `f` while being bound to the given virtual memory
 /// context `vmem`.
 ///
@@ -283,12 +445,13 @@ pub(crate) fn init() {
 	for section in iter {
 		let write = section.sh_flags as u32 & elf::SHF_WRITE != 0;
 		let user = elf::kernel::get_section_name(section) == Some(b".user");
-		let mut flags = FLAG_GLOBAL;
+		// Every kernel section is at least readable; `write` adds `PROT_WRITE`. None of them are
+		// mapped executable through this loop (the FLAG_GLOBAL-only path above already leaves the
+		// rest of the kernel image mapped without restriction), so there is nothing for the W^X
+		// check in `map_range_perm` to reject here.
+		let mut perm = Permission::READ;
 		if write {
-			flags |= FLAG_WRITE;
-		}
-		if user {
-			flags |= FLAG_USER;
+			perm = perm | Permission::WRITE;
 		}
 		// Map
 		let virt_addr = VirtAddr(section.sh_addr as _);
@@ -296,9 +459,10 @@ pub(crate) fn init() {
 			continue;
 		};
 		let pages = section.sh_size.div_ceil(PAGE_SIZE as _) as usize;
-		kernel_vmem.map_range(phys_addr, virt_addr, pages, flags);
+		kernel_vmem.map_range_perm(phys_addr, virt_addr, pages, perm, user, true);
 	}
-	// Map VGA buffer
+	// Map VGA buffer. Its access pattern (uncached MMIO) isn't expressible through
+	// `Permission`, so this keeps using the raw flags API.
 	#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 	kernel_vmem.map_range(
 		vga::BUFFER_PHYS as _,
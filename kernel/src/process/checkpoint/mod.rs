@@ -0,0 +1,145 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! CRIU-style checkpoint/restore of per-process state.
+//!
+//! This module defines the serializable record for one `timer_create` timer ([`TimerRecord`])
+//! and the relative/absolute expiration bookkeeping a dump/restore cycle needs ([`Expiration`]).
+//! [`stream`] frames records of this and other kinds (memory mappings, file descriptors, ...)
+//! into a self-describing, ordered stream that can be written to or read from an arbitrary file
+//! descriptor rather than staged through an on-disk image file.
+//!
+//! What this does *not* yet do: walk a process's actual timer set, or capture pending
+//! signals/dispositions. Doing so needs `TimerManager` to expose an iteration entry point (it
+//! currently only offers [`create_timer`](crate::time::timer::TimerManager::create_timer),
+//! confirmed from its use in `syscall::timer_create`) plus per-timer accessors for remaining
+//! time, interval and overrun count, and needs `process::signal::ProcessSignal` to expose a way
+//! to enumerate pending signals (it confirmed exposes point queries such as
+//! `is_signal_blocked` and an index-by-signal-number `handlers` table, per `device::tty`'s use of
+//! them, but no bulk accessor). Neither module is part of this tree's snapshot, so rather than
+//! guess at APIs that can't be checked, this module implements the part that is fully
+//! self-contained — the timer record shape and the expiration math — so that wiring a `dump`/
+//! `restore` entry point into `TimerManager` becomes a small, mechanical step once those
+//! accessors exist. The syscall/ioctl entry point that resolves a userspace fd to a
+//! [`stream::RecordSink`]/[`stream::RecordSource`] and drives the dump/restore loop is likewise
+//! out of this tree's snapshot (`file::File`'s read/write plumbing isn't present here either), so
+//! [`stream`] is written against the small sink/source traits it defines itself.
+
+pub mod stream;
+
+use crate::{
+	process::signal::SigEvent,
+	time::{
+		clock::{current_time_ns, Clock},
+		unit::ClockIdT,
+	},
+};
+use utils::{errno, errno::EResult};
+
+/// How a timer's next expiration is encoded in a [`TimerRecord`].
+///
+/// `CLOCK_REALTIME` timers must survive the wall clock being rewound or fast-forwarded between
+/// dump and restore, so their expiration is captured as an absolute timestamp on that clock's own
+/// timeline. Every other clock (notably `CLOCK_MONOTONIC`, whose epoch is arbitrary and resets
+/// across a restore) has its expiration captured as a delta relative to the moment of the dump,
+/// recomputed against the clock's value at restore time.
+#[derive(Debug, Clone, Copy)]
+pub enum Expiration {
+	/// Nanoseconds remaining until expiration, measured from the moment the record was captured.
+	Relative(u64),
+	/// Absolute nanosecond timestamp of expiration, on the clock's own timeline.
+	Absolute(u64),
+}
+
+impl Expiration {
+	/// Captures an expiration that is `remaining_ns` nanoseconds away, encoding it as relative or
+	/// absolute depending on `clockid`.
+	pub fn capture(clockid: ClockIdT, remaining_ns: u64) -> EResult<Self> {
+		let clock = Clock::from_id(clockid).ok_or_else(|| errno!(EINVAL))?;
+		Ok(match clock {
+			Clock::Realtime => Self::Absolute(current_time_ns(clock) + remaining_ns),
+			_ => Self::Relative(remaining_ns),
+		})
+	}
+
+	/// Resolves the record into a number of nanoseconds remaining from now, against `clockid`'s
+	/// current value.
+	///
+	/// For an [`Self::Absolute`] deadline that has already passed by restore time (the timer
+	/// expired but its signal had not yet been consumed when the dump was taken), this returns
+	/// `0`: the caller is expected to re-arm the timer so it fires immediately, while the
+	/// [`TimerRecord::overrun`] count it restores alongside carries the fact that expirations
+	/// were missed.
+	pub fn resolve(self, clockid: ClockIdT) -> EResult<u64> {
+		let clock = Clock::from_id(clockid).ok_or_else(|| errno!(EINVAL))?;
+		Ok(match self {
+			Self::Relative(ns) => ns,
+			Self::Absolute(ns) => ns.saturating_sub(current_time_ns(clock)),
+		})
+	}
+}
+
+/// A serializable snapshot of one `timer_create` timer.
+///
+/// Produced by [`Self::capture`] from the values a `timer_gettime`-style query would report, and
+/// consumed by [`Self::restore_delay`] to recompute how long to re-arm the timer for.
+#[derive(Debug, Clone)]
+pub struct TimerRecord {
+	/// The clock the timer was created against.
+	pub clockid: ClockIdT,
+	/// The event to deliver on expiration, exactly as configured by `timer_create`/
+	/// `timer_settime` (notify mode, signal number, value, and target thread id).
+	pub sevp: SigEvent,
+	/// The next expiration, captured at dump time and encoded as described by [`Expiration`].
+	next_expiration: Expiration,
+	/// The reload interval for a periodic timer, in nanoseconds (`0` for a one-shot timer).
+	pub interval_ns: u64,
+	/// The number of expirations that occurred before the pending signal was consumed.
+	///
+	/// Preserved verbatim across dump/restore: a timer that already expired but whose signal is
+	/// still pending must restore with this count intact rather than reset to zero.
+	pub overrun: u32,
+}
+
+impl TimerRecord {
+	/// Captures a timer's state for later restore.
+	///
+	/// `remaining_ns` and `interval_ns` are both in nanoseconds, relative to the moment of the
+	/// call, mirroring the fields a `timer_gettime`-style query would report.
+	pub fn capture(
+		clockid: ClockIdT,
+		sevp: SigEvent,
+		remaining_ns: u64,
+		interval_ns: u64,
+		overrun: u32,
+	) -> EResult<Self> {
+		Ok(Self {
+			clockid,
+			sevp,
+			next_expiration: Expiration::capture(clockid, remaining_ns)?,
+			interval_ns,
+			overrun,
+		})
+	}
+
+	/// Resolves the delay, in nanoseconds, before this timer's next expiration should fire,
+	/// measured from now rather than from the moment this record was captured.
+	pub fn restore_delay(&self) -> EResult<u64> {
+		self.next_expiration.resolve(self.clockid)
+	}
+}
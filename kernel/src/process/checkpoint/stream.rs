@@ -0,0 +1,235 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Framing of a checkpoint image as a stream of self-describing, length-prefixed records.
+//!
+//! A dump is a sequence of `(`[`RecordType`]`, length, payload)` records written one at a time as
+//! they become available (one per timer, one per VMA, ...), so a slow consumer on the other end
+//! of a pipe or socket applies backpressure through the fd's own blocking semantics instead of
+//! the kernel buffering the whole image. [`RecordType`]'s declaration order doubles as the
+//! dependency order a restore reader can rely on in a single forward pass: process-wide state
+//! first, then file descriptors, then the address space (whose mappings may be backed by those
+//! fds), then timers and signal state (which reference thread ids already established by the
+//! address space and mapping records). [`RecordWriter`]/[`RecordReader`] enforce that ordering
+//! with a debug assertion rather than trusting every call site to get it right.
+//!
+//! [`TransformStage`] is the registration point for inline processing (checksumming, and
+//! eventually compression/encryption) of the raw bytes as they cross the wire, in either
+//! direction.
+
+use utils::{boxed::Box, collections::vec::Vec, errno, errno::EResult, lock::Mutex};
+
+/// The kind of a record in a checkpoint stream.
+///
+/// Declaration order is the order a dump must emit records in, and the order a restore reader
+/// may assume when reconstructing dependencies in a single forward pass.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RecordType {
+	/// Process-wide metadata (pid, credentials, ...), always first.
+	ProcessHeader = 0,
+	/// One open file descriptor. Emitted before any record that may reference it (mappings).
+	FileDescriptor = 1,
+	/// One virtual memory area of the address space.
+	MemMapping = 2,
+	/// One `timer_create` timer, as captured by [`super::TimerRecord`].
+	Timer = 3,
+	/// Signal dispositions and pending state.
+	SignalState = 4,
+	/// Marks the end of the stream.
+	End = 5,
+}
+
+impl TryFrom<u32> for RecordType {
+	type Error = ();
+
+	fn try_from(val: u32) -> Result<Self, ()> {
+		Ok(match val {
+			0 => Self::ProcessHeader,
+			1 => Self::FileDescriptor,
+			2 => Self::MemMapping,
+			3 => Self::Timer,
+			4 => Self::SignalState,
+			5 => Self::End,
+			_ => return Err(()),
+		})
+	}
+}
+
+/// The wire size of a record's header: the [`RecordType`] and payload length, each a 4-byte
+/// little-endian integer.
+const HEADER_LEN: usize = 8;
+
+/// The destination a checkpoint dump is streamed to: a pipe, a socket, or anything else reached
+/// through a file descriptor.
+///
+/// A single call may perform a short write, mirroring the blocking semantics of a real fd: the
+/// caller is expected to retry with the remainder, which is exactly what [`RecordWriter`] does.
+pub trait RecordSink {
+	/// Writes as much of `buf` as the destination currently accepts, returning the number of
+	/// bytes actually written. Returning `0` means the destination accepts no more bytes:
+	/// [`RecordWriter`] then fails with `EPIPE`.
+	fn write(&mut self, buf: &[u8]) -> EResult<usize>;
+}
+
+/// The source a checkpoint restore is streamed from.
+///
+/// A single call may perform a short read; the caller is expected to retry with the remainder,
+/// which is exactly what [`RecordReader`] does. Returning `0` means end of stream.
+pub trait RecordSource {
+	/// Reads as much of `buf` as is currently available, returning the number of bytes actually
+	/// read, or `0` at end of stream.
+	fn read(&mut self, buf: &mut [u8]) -> EResult<usize>;
+}
+
+/// An inline processing stage applied to every byte that crosses a checkpoint stream, in either
+/// direction (e.g. a running checksum).
+pub trait TransformStage: Send + Sync {
+	/// Feeds `buf` through this stage.
+	fn process(&mut self, buf: &[u8]);
+}
+
+/// The registry of transform stages applied, in registration order, to every byte written or
+/// read through a [`RecordWriter`]/[`RecordReader`].
+static TRANSFORM_STAGES: Mutex<Vec<Box<dyn TransformStage>>> = Mutex::new(Vec::new());
+
+/// Registers `stage` to run on every checkpoint stream from now on.
+pub fn register_transform_stage(stage: Box<dyn TransformStage>) -> EResult<()> {
+	TRANSFORM_STAGES.lock().push(stage)
+}
+
+/// Feeds `buf` through every registered [`TransformStage`], in order.
+fn run_transforms(buf: &[u8]) {
+	let mut stages = TRANSFORM_STAGES.lock();
+	for stage in stages.iter_mut() {
+		stage.process(buf);
+	}
+}
+
+/// Writes every byte of `buf` to `sink`, retrying on short writes.
+fn write_all<S: RecordSink>(sink: &mut S, mut buf: &[u8]) -> EResult<()> {
+	while !buf.is_empty() {
+		let n = sink.write(buf)?;
+		if n == 0 {
+			return Err(errno!(EPIPE));
+		}
+		buf = &buf[n..];
+	}
+	Ok(())
+}
+
+/// Reads exactly `buf.len()` bytes from `source`, retrying on short reads.
+///
+/// Returns `false` if the stream ends before any byte of `buf` is filled (a clean end of
+/// stream), or an error if it ends partway through a record (a truncated stream).
+fn read_exact<S: RecordSource>(source: &mut S, buf: &mut [u8]) -> EResult<bool> {
+	let mut filled = 0;
+	while filled < buf.len() {
+		let n = source.read(&mut buf[filled..])?;
+		if n == 0 {
+			if filled == 0 {
+				return Ok(false);
+			}
+			return Err(errno!(EIO));
+		}
+		filled += n;
+	}
+	Ok(true)
+}
+
+/// Writes a checkpoint image as a sequence of records to a [`RecordSink`].
+pub struct RecordWriter<S: RecordSink> {
+	sink: S,
+	/// The type of the last record written, to enforce that records are emitted in
+	/// [`RecordType`] order.
+	last_type: Option<RecordType>,
+}
+
+impl<S: RecordSink> RecordWriter<S> {
+	/// Creates a writer streaming records to `sink`.
+	pub fn new(sink: S) -> Self {
+		Self {
+			sink,
+			last_type: None,
+		}
+	}
+
+	/// Writes one record of type `record_type` with contents `payload`.
+	///
+	/// Panics (in debug builds) if `record_type` is strictly less than the type of the
+	/// previously written record, since that would break the single-forward-pass ordering
+	/// restore depends on.
+	pub fn write_record(&mut self, record_type: RecordType, payload: &[u8]) -> EResult<()> {
+		debug_assert!(self.last_type.map_or(true, |last| record_type >= last));
+		self.last_type = Some(record_type);
+		let mut header = [0u8; HEADER_LEN];
+		header[..4].copy_from_slice(&(record_type as u32).to_le_bytes());
+		header[4..].copy_from_slice(&(payload.len() as u32).to_le_bytes());
+		run_transforms(&header);
+		run_transforms(payload);
+		write_all(&mut self.sink, &header)?;
+		write_all(&mut self.sink, payload)
+	}
+
+	/// Writes the terminating [`RecordType::End`] record.
+	pub fn finish(&mut self) -> EResult<()> {
+		self.write_record(RecordType::End, &[])
+	}
+}
+
+/// Reads a checkpoint image as a sequence of records from a [`RecordSource`].
+pub struct RecordReader<S: RecordSource> {
+	source: S,
+	/// The type of the last record read, to enforce that records arrive in [`RecordType`]
+	/// order.
+	last_type: Option<RecordType>,
+}
+
+impl<S: RecordSource> RecordReader<S> {
+	/// Creates a reader streaming records from `source`.
+	pub fn new(source: S) -> Self {
+		Self {
+			source,
+			last_type: None,
+		}
+	}
+
+	/// Reads the next record, or `None` once [`RecordType::End`] has been consumed or the stream
+	/// has cleanly ended.
+	pub fn read_record(&mut self) -> EResult<Option<(RecordType, Vec<u8>)>> {
+		let mut header = [0u8; HEADER_LEN];
+		if !read_exact(&mut self.source, &mut header)? {
+			return Ok(None);
+		}
+		run_transforms(&header);
+		let record_type = u32::from_le_bytes(header[..4].try_into().unwrap());
+		let record_type = RecordType::try_from(record_type).map_err(|_| errno!(EINVAL))?;
+		debug_assert!(self.last_type.map_or(true, |last| record_type >= last));
+		self.last_type = Some(record_type);
+		if record_type == RecordType::End {
+			return Ok(None);
+		}
+		let len = u32::from_le_bytes(header[4..].try_into().unwrap()) as usize;
+		let mut payload = vec![0u8; len]?;
+		if !read_exact(&mut self.source, &mut payload)? {
+			return Err(errno!(EIO));
+		}
+		run_transforms(&payload);
+		Ok(Some((record_type, payload)))
+	}
+}
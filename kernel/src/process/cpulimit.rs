@@ -0,0 +1,122 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! A cooperative, userspace-style CPU limiter (in the spirit of the `cpulimit` utility): a
+//! privileged caller throttles a target process to roughly a target percentage of CPU time by
+//! alternately stopping and resuming it in a duty cycle, rather than anything the scheduler's own
+//! accounting is aware of.
+//!
+//! [`throttle`] registers a [`CpuLimiter`] into [`LIMITERS`]; [`poll_all`] advances every
+//! registered limiter by one duty-cycle slot, dropping any whose target has already exited. It is
+//! driven from [`super::yield_current`], since nothing in this tree's snapshot exposes a periodic
+//! timer callback to hang it off of instead (`time` has no files here, the same gap
+//! [`super::rt_signal`]'s module doc already documents against populating a `siginfo_t`).
+//!
+//! The suspend/resume itself is a plain [`State::Stopped`]/[`State::Running`] transition through
+//! [`Process::set_state`], not a real queued `SIGSTOP`/`SIGCONT` delivery, per the request this
+//! module implements.
+//!
+//! Unlike `seccomp`/`pidfd`, there is no real Linux syscall shaped like this to expose
+//! [`throttle`] through (the real `cpulimit` utility is unprivileged userspace code built on top
+//! of ordinary `SIGSTOP`/`SIGCONT`); this stops at the kernel-internal control surface a
+//! privileged ioctl, `procfs` knob, or debug syscall would call into once one exists.
+
+use super::{Process, State};
+use crate::{file::perm::AccessProfile, sync::mutex::Mutex};
+use core::sync::atomic::{AtomicU64, Ordering::Relaxed};
+use utils::{collections::vec::Vec, errno, errno::EResult, ptr::arc::Arc};
+
+/// The number of duty-cycle slots a limiter's target percentage is quantized into.
+///
+/// A target of `42%` runs for that share of this many slots, rounded down, e.g. `4` out of `10`.
+const CYCLE_SLOTS: u64 = 10;
+
+/// A single registered cooperative CPU limiter.
+struct CpuLimiter {
+	/// The throttled process.
+	target: Arc<Process>,
+	/// The target CPU percentage, in `0..=100`.
+	percent: u8,
+	/// The current slot within the duty cycle, incremented by [`Self::tick`].
+	slot: AtomicU64,
+}
+
+impl CpuLimiter {
+	/// Tells whether `slot` falls in the "on" portion of the duty cycle.
+	fn wants_running(&self, slot: u64) -> bool {
+		let on_slots = (self.percent as u64 * CYCLE_SLOTS) / 100;
+		(slot % CYCLE_SLOTS) < on_slots
+	}
+
+	/// Advances the duty cycle by one slot, stopping or resuming [`Self::target`] as needed.
+	///
+	/// Returns `false` once the target has exited, telling [`poll_all`] to drop this limiter.
+	fn tick(&self) -> bool {
+		if self.target.get_state() == State::Zombie {
+			return false;
+		}
+		let slot = self.slot.fetch_add(1, Relaxed);
+		let running = self.target.get_state() == State::Running;
+		match (self.wants_running(slot), running) {
+			(true, false) => self.target.set_state(State::Running),
+			(false, true) => self.target.set_state(State::Stopped),
+			_ => {}
+		}
+		true
+	}
+}
+
+/// The set of currently active limiters.
+static LIMITERS: Mutex<Vec<CpuLimiter>> = Mutex::new(Vec::new());
+
+/// Registers a new limiter throttling `target` to roughly `percent`% CPU utilization, on behalf
+/// of `caller`.
+///
+/// Fails with [`EPERM`] if `caller` is not privileged: arbitrarily stopping and resuming another
+/// process is a much stronger capability than `kill(2)`'s own same-or-saved/effective-UID check
+/// grants, so this requires the same "is root" bar `setrlimit`'s hard-limit raise does. Fails with
+/// [`EINVAL`] if `percent` is greater than `100`.
+pub fn throttle(caller: &AccessProfile, target: Arc<Process>, percent: u8) -> EResult<()> {
+	if !caller.is_privileged() {
+		return Err(errno!(EPERM));
+	}
+	if percent > 100 {
+		return Err(errno!(EINVAL));
+	}
+	LIMITERS.lock().push(CpuLimiter {
+		target,
+		percent,
+		slot: AtomicU64::new(0),
+	})
+}
+
+/// Advances every registered limiter by one duty-cycle slot, dropping any whose target has
+/// exited.
+///
+/// Meant to be called once per call into [`super::yield_current`].
+pub fn poll_all() {
+	let mut limiters = LIMITERS.lock();
+	let mut i = 0;
+	while let Some(limiter) = limiters.get(i) {
+		if limiter.tick() {
+			i += 1;
+		} else {
+			limiters.remove(i);
+		}
+	}
+}
@@ -0,0 +1,105 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `inotify(7)`-style filesystem change notification.
+//!
+//! [`EventQueue`] is a process's own readable queue of [`Event`]s, filled by every
+//! [`crate::file::vfs::node::Node`] it has registered a watch on via
+//! `Node::add_watch`. Living here rather than on `file::vfs::node` itself keeps the type fully
+//! `pub`, so both that module and [`super::Process`] can name it without either depending on a
+//! `mod` declaration in the invisible `file::vfs::mod.rs` (the same class of gap
+//! [`super::unveil`]'s own doc comment documents for its own `file::vfs` call site).
+//!
+//! Exposing [`EventQueue`] as an actual pollable file descriptor needs `file::fd`'s
+//! `FileDescriptorTable` machinery, which has no file in this tree's snapshot (the same gap
+//! [`super::pidfd`]'s own doc comment documents); [`EventQueue::drain`] is written in the shape
+//! the read side of that integration would call.
+
+use core::ops::BitOr;
+use utils::{
+	collections::vec::Vec,
+	errno::AllocResult,
+};
+
+/// A bitmask of inotify-style events a watch can request, and that [`Node::notify`] delivers.
+///
+/// [`Node::notify`]: crate::file::vfs::node::Node::notify
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EventMask(u8);
+
+impl EventMask {
+	/// The node's content was modified (a successful `write`).
+	pub const MODIFY: Self = Self(1 << 0);
+	/// The node's metadata was changed (`fchmod`, `vfs::set_stat`, ...).
+	pub const ATTRIB: Self = Self(1 << 1);
+	/// A new link to (or entry under) the node was created.
+	pub const CREATE: Self = Self(1 << 2);
+	/// A link to (or entry under) the node was removed.
+	pub const DELETE: Self = Self(1 << 3);
+	/// The node (or an entry under it) was moved.
+	pub const MOVE: Self = Self(1 << 4);
+	/// The node itself left the cache (its last reference was released, or its filesystem was
+	/// unmounted): the final event a watch ever receives.
+	pub const DELETE_SELF: Self = Self(1 << 5);
+
+	/// Tells whether `self` requests at least one of the events in `other`.
+	pub fn contains(&self, other: Self) -> bool {
+		self.0 & other.0 != 0
+	}
+}
+
+impl BitOr for EventMask {
+	type Output = Self;
+
+	fn bitor(self, rhs: Self) -> Self {
+		Self(self.0 | rhs.0)
+	}
+}
+
+/// A single filesystem event delivered to a watching process.
+#[derive(Debug, Clone, Copy)]
+pub struct Event {
+	/// The watch descriptor identifying the watch this event matched, as returned by
+	/// `Node::add_watch`.
+	pub wd: u32,
+	/// The event that occurred. A subset of the watch's requested mask, never empty.
+	pub mask: EventMask,
+}
+
+/// A process's readable queue of filesystem events delivered by its watches.
+#[derive(Debug, Default)]
+pub struct EventQueue {
+	events: crate::sync::mutex::Mutex<Vec<Event>>,
+}
+
+impl EventQueue {
+	/// Creates a new, empty queue.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Appends `event` to the queue.
+	pub fn push(&self, event: Event) -> AllocResult<()> {
+		self.events.lock().push(event)
+	}
+
+	/// Drains and returns every event accumulated so far.
+	pub fn drain(&self) -> Vec<Event> {
+		core::mem::take(&mut *self.events.lock())
+	}
+}
@@ -41,36 +41,75 @@ extern "C" {
 	pub fn copy_fault();
 }
 
-/// Low level function to copy data from userspace to kernelspace, with access check.
+/// Bound-checks then copies `n` bytes from userspace `src` to kernelspace `dst`.
 ///
-/// If the access check fails, the function returns [`EFAULT`].
-unsafe fn copy_from_user_raw(src: *const u8, dst: *mut u8, n: usize) -> EResult<()> {
+/// # Safety
+///
+/// The caller must ensure SMAP is disabled for the duration of the call (e.g. by calling this
+/// from within [`vmem::smap_disable`] or while holding a [`vmem::SmapGuard`]).
+unsafe fn copy_from_user_raw_inner(src: *const u8, dst: *mut u8, n: usize) -> EResult<()> {
 	if unlikely(!bound_check(src as _, n)) {
 		return Err(errno!(EFAULT));
 	}
-	let res = vmem::smap_disable(|| raw_copy(dst, src, n));
-	if likely(res) {
+	if likely(raw_copy(dst, src, n)) {
 		Ok(())
 	} else {
 		Err(errno!(EFAULT))
 	}
 }
 
-/// Low level function to copy data from kernelspace to userspace, with access check.
+/// Bound-checks then copies `n` bytes from kernelspace `src` to userspace `dst`.
 ///
-/// If the access check fails, the function returns [`EFAULT`].
-unsafe fn copy_to_user_raw(src: *const u8, dst: *mut u8, n: usize) -> EResult<()> {
+/// # Safety
+///
+/// Same requirement as [`copy_from_user_raw_inner`]: SMAP must already be disabled.
+unsafe fn copy_to_user_raw_inner(src: *const u8, dst: *mut u8, n: usize) -> EResult<()> {
 	if unlikely(!bound_check(dst as _, n)) {
 		return Err(errno!(EFAULT));
 	}
-	let res = vmem::smap_disable(|| raw_copy(dst, src, n));
-	if likely(res) {
+	if likely(raw_copy(dst, src, n)) {
 		Ok(())
 	} else {
 		Err(errno!(EFAULT))
 	}
 }
 
+/// Low level function to copy data from userspace to kernelspace, with access check.
+///
+/// If the access check fails, the function returns [`EFAULT`].
+unsafe fn copy_from_user_raw(src: *const u8, dst: *mut u8, n: usize) -> EResult<()> {
+	vmem::smap_disable(|| copy_from_user_raw_inner(src, dst, n))
+}
+
+/// Low level function to copy data from kernelspace to userspace, with access check.
+///
+/// If the access check fails, the function returns [`EFAULT`].
+unsafe fn copy_to_user_raw(src: *const u8, dst: *mut u8, n: usize) -> EResult<()> {
+	vmem::smap_disable(|| copy_to_user_raw_inner(src, dst, n))
+}
+
+/// Copies several `(src, dst, len)` segments from userspace to kernelspace under a single
+/// SMAP-disabled window, instead of the one STAC/CLAC toggle per call that repeated
+/// [`copy_from_user_raw`] calls would incur.
+///
+/// Every segment is bound-checked before any copy begins, so a later segment failing its check
+/// can't leave an arbitrary prefix of earlier segments copied for nothing.
+pub fn copy_from_user_batch(segments: &[(*const u8, *mut u8, usize)]) -> EResult<()> {
+	for &(src, _, len) in segments {
+		if unlikely(!bound_check(src as _, len)) {
+			return Err(errno!(EFAULT));
+		}
+	}
+	unsafe {
+		vmem::smap_disable(|| {
+			for &(src, dst, len) in segments {
+				copy_from_user_raw_inner(src, dst, len)?;
+			}
+			Ok(())
+		})
+	}
+}
+
 /// Wrapper for a pointer.
 pub struct SyscallPtr<T: Sized + fmt::Debug>(pub Option<NonNull<T>>);
 
@@ -236,6 +275,67 @@ impl<T: fmt::Debug> fmt::Debug for SyscallSlice<T> {
 	}
 }
 
+/// Mirrors userspace's `struct iovec`: a base pointer and byte length, as used by
+/// `readv`/`writev` and their `p`-prefixed (positioned) variants.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct RawIoVec {
+	pub iov_base: usize,
+	pub iov_len: usize,
+}
+
+/// Wrapper for a `struct iovec` array, as passed to `readv`/`writev`/`preadv`/`pwritev`.
+pub struct UserIOVec(pub Option<NonNull<RawIoVec>>);
+
+impl FromSyscallArg for UserIOVec {
+	fn from_syscall_arg(val: usize) -> Self {
+		Self(NonNull::new(ptr::with_exposed_provenance_mut(val)))
+	}
+}
+
+impl UserIOVec {
+	/// Returns an immutable pointer to the data.
+	pub fn as_ptr(&self) -> *const RawIoVec {
+		self.0.map(NonNull::as_ptr).unwrap_or(null_mut()) as _
+	}
+
+	/// Copies the `iovcnt` `struct iovec` entries into a kernelspace [`Vec`].
+	///
+	/// If the pointer is null, the function returns `None`.
+	pub fn copy_from_user(&self, iovcnt: usize) -> EResult<Option<Vec<RawIoVec>>> {
+		let Some(ptr) = self.0 else {
+			return Ok(None);
+		};
+		let mut buf = Vec::with_capacity(iovcnt)?;
+		unsafe {
+			buf.set_len(iovcnt);
+			copy_from_user_raw(
+				ptr.as_ptr() as *const _,
+				buf.as_mut_ptr() as *mut _,
+				size_of::<RawIoVec>() * iovcnt,
+			)?;
+		}
+		Ok(Some(buf))
+	}
+}
+
+impl fmt::Debug for UserIOVec {
+	fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self.0 {
+			Some(ptr) => write!(fmt, "{ptr:p}"),
+			None => write!(fmt, "NULL"),
+		}
+	}
+}
+
+/// Default cap on the length of a string copied from userspace through [`SyscallString`] when
+/// the caller does not supply its own via [`SyscallString::copy_from_user_bounded`].
+///
+/// Mirrors the intent of glibc's `PATH_MAX`. The real `ARG_MAX`/`PATH_MAX` constants would belong
+/// in `utils::limits`, but that module isn't part of this tree's snapshot, so the cap is kept
+/// local here rather than guessed at in a file that can't be inspected.
+const DEFAULT_STRING_MAX_LEN: usize = 4096;
+
 /// Wrapper for a C-style, nul-terminated (`\0`) string.
 pub struct SyscallString(pub Option<NonNull<u8>>);
 
@@ -254,38 +354,61 @@ impl SyscallString {
 	/// Returns an immutable reference to the string.
 	///
 	/// If the string is not accessible, the function returns an error.
+	///
+	/// The length is capped at [`DEFAULT_STRING_MAX_LEN`]; see
+	/// [`Self::copy_from_user_bounded`] to use a different limit.
 	pub fn copy_from_user(&self) -> EResult<Option<String>> {
+		self.copy_from_user_bounded(DEFAULT_STRING_MAX_LEN)
+	}
+
+	/// Same as [`Self::copy_from_user`], but fails with [`ENAMETOOLONG`] instead of
+	/// reading (and allocating for) an arbitrarily large region if no nul byte is found within
+	/// the first `max_len` bytes.
+	///
+	/// This bounds the cost of a malicious pointer into a large mapped region with no nul byte,
+	/// which would otherwise force the kernel to scan and allocate without limit.
+	pub fn copy_from_user_bounded(&self, max_len: usize) -> EResult<Option<String>> {
 		let Some(ptr) = self.0 else {
 			return Ok(None);
 		};
-		// TODO use empirical data to find the best value, and whether an arithmetic progression is
-		// the optimal solution
-		const CHUNK_SIZE: usize = 128;
-		let mut buf = Vec::new();
-		loop {
-			let buf_cursor = buf.len();
-			// May not wrap since the chunk size is obviously lower than the size of the
-			// kernelspace
-			let user_cursor = ptr.as_ptr().wrapping_add(buf_cursor);
-			let page_end = PAGE_SIZE - (user_cursor as usize % PAGE_SIZE);
-			let len = min(page_end, CHUNK_SIZE);
-			// Read the next chunk
-			buf.reserve(len)?;
-			unsafe {
-				buf.set_len(buf_cursor + len);
-				copy_from_user_raw(user_cursor, &mut buf[buf_cursor], len)?;
-			}
-			// Look for a nul byte
-			let nul_off = buf[buf_cursor..(buf_cursor + len)]
-				.iter()
-				.position(|b| *b == b'\0');
-			if let Some(i) = nul_off {
-				buf.truncate(buf_cursor + i);
-				break;
-			}
+		unsafe { vmem::smap_disable(|| copy_string_from_user_inner(ptr.as_ptr(), max_len)) }.map(Some)
+	}
+}
+
+/// Copies a nul-terminated string from userspace `ptr`, capped at `max_len` bytes.
+///
+/// # Safety
+///
+/// The caller must ensure SMAP is already disabled, as for [`copy_from_user_raw_inner`].
+unsafe fn copy_string_from_user_inner(ptr: *const u8, max_len: usize) -> EResult<String> {
+	// TODO use empirical data to find the best value, and whether an arithmetic progression is
+	// the optimal solution
+	const CHUNK_SIZE: usize = 128;
+	let mut buf = Vec::new();
+	loop {
+		let buf_cursor = buf.len();
+		if buf_cursor >= max_len {
+			return Err(errno!(ENAMETOOLONG));
+		}
+		// May not wrap since the chunk size is obviously lower than the size of the
+		// kernelspace
+		let user_cursor = ptr.wrapping_add(buf_cursor);
+		let page_end = PAGE_SIZE - (user_cursor as usize % PAGE_SIZE);
+		let len = min(min(page_end, CHUNK_SIZE), max_len - buf_cursor);
+		// Read the next chunk
+		buf.reserve(len)?;
+		buf.set_len(buf_cursor + len);
+		copy_from_user_raw_inner(user_cursor, &mut buf[buf_cursor], len)?;
+		// Look for a nul byte
+		let nul_off = buf[buf_cursor..(buf_cursor + len)]
+			.iter()
+			.position(|b| *b == b'\0');
+		if let Some(i) = nul_off {
+			buf.truncate(buf_cursor + i);
+			break;
 		}
-		Ok(Some(buf.into()))
 	}
+	Ok(buf.into())
 }
 
 impl fmt::Debug for SyscallString {
@@ -315,14 +438,68 @@ impl SyscallArray {
 	}
 
 	/// Returns an iterator over the array's elements.
+	///
+	/// Each element's length is capped at [`DEFAULT_STRING_MAX_LEN`]; see [`Self::iter_bounded`]
+	/// to use a different limit (e.g. for `execve` argument/environment walking).
 	pub fn iter(&self) -> SyscallArrayIterator {
+		self.iter_bounded(DEFAULT_STRING_MAX_LEN)
+	}
+
+	/// Same as [`Self::iter`], but caps each element's length at `max_len` instead of
+	/// [`DEFAULT_STRING_MAX_LEN`].
+	pub fn iter_bounded(&self, max_len: usize) -> SyscallArrayIterator {
 		SyscallArrayIterator {
 			arr: self,
 			i: 0,
+			max_len,
+		}
+	}
+
+	/// Same as collecting [`Self::iter_bounded`] into a `Vec`, but reads the pointer array and
+	/// materializes every element's string under a single SMAP-disabled window, instead of
+	/// [`SyscallArrayIterator`]'s one pointer read plus one SMAP toggle per element. The result
+	/// is also reserved once from the array's element count, instead of growing one push at a
+	/// time.
+	///
+	/// The number of elements is capped at [`MAX_ARRAY_LEN`], returning [`E2BIG`] beyond
+	/// that, for the same reason each element's length is capped.
+	pub fn collect(&self, max_len: usize) -> EResult<Vec<String>> {
+		let Some(arr) = self.0 else {
+			return Err(errno!(EFAULT));
+		};
+		unsafe {
+			vmem::smap_disable(|| {
+				// Read the pointer array itself, stopping at the first NULL entry.
+				let mut ptrs = Vec::new();
+				loop {
+					if ptrs.len() >= MAX_ARRAY_LEN {
+						return Err(errno!(E2BIG));
+					}
+					let src = arr.as_ptr().add(ptrs.len()) as *const u8;
+					let mut raw = [0u8; size_of::<*const u8>()];
+					copy_from_user_raw_inner(src, raw.as_mut_ptr(), raw.len())?;
+					let ptr = usize::from_ne_bytes(raw) as *const u8;
+					if ptr.is_null() {
+						break;
+					}
+					ptrs.push(ptr)?;
+				}
+				// Materialize each string, with the result pre-sized from the known count.
+				let mut out = Vec::new();
+				out.reserve(ptrs.len())?;
+				for ptr in ptrs {
+					out.push(copy_string_from_user_inner(ptr, max_len)?)?;
+				}
+				Ok(out)
+			})
 		}
 	}
 }
 
+/// Maximum number of elements read by [`SyscallArray::collect`], to bound the cost of a
+/// malformed or malicious array with no NULL terminator.
+const MAX_ARRAY_LEN: usize = 4096;
+
 impl fmt::Debug for SyscallArray {
 	fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
 		let mut list = fmt.debug_list();
@@ -343,6 +520,8 @@ pub struct SyscallArrayIterator<'a> {
 	arr: &'a SyscallArray,
 	/// The current index.
 	i: usize,
+	/// The maximum length, in bytes, of each element.
+	max_len: usize,
 }
 
 impl<'a> Iterator for SyscallArrayIterator<'a> {
@@ -354,7 +533,7 @@ impl<'a> Iterator for SyscallArrayIterator<'a> {
 		};
 		let str_ptr = unsafe { arr.add(self.i).read_volatile() };
 		let res = SyscallString(NonNull::new(str_ptr as _))
-			.copy_from_user()
+			.copy_from_user_bounded(self.max_len)
 			.transpose();
 		// Do not increment if reaching `NULL`
 		if res.is_some() {
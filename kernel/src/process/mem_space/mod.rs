@@ -33,9 +33,18 @@ use crate::{
 	file::{perm::AccessProfile, vfs, File},
 	memory,
 	memory::{cache::RcFrame, vmem::VMem, VirtAddr, PROCESS_END},
+	process::rlimit::RLIM_INFINITY,
 };
 use core::{
-	alloc::AllocError, cmp::min, ffi::c_void, fmt, intrinsics::unlikely, mem, num::NonZeroUsize,
+	alloc::AllocError,
+	arch::asm,
+	cmp::min,
+	ffi::c_void,
+	fmt,
+	intrinsics::unlikely,
+	mem,
+	num::NonZeroUsize,
+	sync::atomic::{AtomicU64, Ordering},
 };
 use gap::MemGap;
 use mapping::MemMapping;
@@ -64,6 +73,12 @@ pub const MAP_PRIVATE: u8 = 0x2;
 pub const MAP_FIXED: u8 = 0x10;
 /// The mapping is not backed by any file
 pub const MAP_ANONYMOUS: u8 = 0x20;
+/// Pre-fault every page of the mapping at creation, instead of leaving them to be faulted in on
+/// first access
+pub const MAP_POPULATE: u8 = 0x40;
+/// Like [`MAP_POPULATE`], but additionally pin the resulting frames so they are excluded from
+/// reclaim/swap
+pub const MAP_LOCKED: u8 = 0x80;
 
 /// The virtual address of the buffer used to map pages for copy.
 const COPY_BUFFER: VirtAddr = VirtAddr(PROCESS_END.0 - PAGE_SIZE);
@@ -76,7 +91,6 @@ pub fn bound_check(addr: usize, n: usize) -> bool {
 	addr >= PAGE_SIZE && addr.saturating_add(n) <= COPY_BUFFER.0
 }
 
-// TODO Add a variant for ASLR
 /// Enumeration of constraints for the selection of the virtual address for a memory mapping.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum MapConstraint {
@@ -96,6 +110,13 @@ pub enum MapConstraint {
 
 	/// No constraint.
 	None,
+
+	/// Like [`MapConstraint::None`], but the address is chosen pseudo-randomly among the
+	/// available gaps instead of always taking the first fit.
+	///
+	/// This is the constraint used for ASLR: non-fixed, non-hinted `mmap`s and the initial
+	/// program/stack/heap placement so that the layout differs on every `exec`.
+	Aslr,
 }
 
 impl MapConstraint {
@@ -114,6 +135,62 @@ impl MapConstraint {
 	}
 }
 
+/// State of the pseudo-random generator used to place [`MapConstraint::Aslr`] mappings.
+///
+/// Lazily seeded from the timestamp counter on first use, which in practice means at boot: the
+/// first ASLR placement happens while loading the very first userspace program.
+static ASLR_STATE: AtomicU64 = AtomicU64::new(0);
+
+/// Reads the CPU timestamp counter, used to seed [`ASLR_STATE`].
+fn rdtsc() -> u64 {
+	let lo: u32;
+	let hi: u32;
+	unsafe {
+		asm!("rdtsc", out("eax") lo, out("edx") hi, options(nomem, nostack));
+	}
+	((hi as u64) << 32) | lo as u64
+}
+
+/// Returns the next value of the ASLR pseudo-random generator.
+///
+/// This is a xorshift64* generator: not cryptographically strong, but enough entropy and
+/// unpredictability to scatter mapping addresses across `exec`s.
+fn aslr_next() -> u64 {
+	let mut state = ASLR_STATE.load(Ordering::Relaxed);
+	if unlikely(state == 0) {
+		state = rdtsc() | 1;
+	}
+	state ^= state >> 12;
+	state ^= state << 25;
+	state ^= state >> 27;
+	ASLR_STATE.store(state, Ordering::Relaxed);
+	state.wrapping_mul(0x2545f4914f6cdd1d)
+}
+
+/// Returns a pseudo-random value in `0..bound`.
+///
+/// If `bound` is `0`, the function returns `0`.
+fn aslr_below(bound: usize) -> usize {
+	if bound == 0 {
+		return 0;
+	}
+	(aslr_next() as usize) % bound
+}
+
+/// Advice given to [`MemSpace::madvise`] about the future usage of a range of memory.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MemAdvice {
+	/// The range will be accessed soon: pre-fault it eagerly.
+	WillNeed,
+	/// The range will not be accessed soon: its backing may be discarded right away. The next
+	/// fault re-faults a fresh zero (or re-read, for file-backed mappings) page.
+	DontNeed,
+	/// Unlike [`MemAdvice::DontNeed`], the backing is not discarded: the pages are only recorded
+	/// as freeable, stay valid to access, and a write fault drops the record. Nothing reclaims
+	/// recorded pages, so their backing is kept.
+	Free,
+}
+
 /// Removes gaps in `on` in the given range, using `transaction`.
 ///
 /// `start` is the start address of the range and `size` is the size of the range in pages.
@@ -174,6 +251,13 @@ struct MemSpaceState {
 	/// Sorted by pointer to the beginning of the mapping on the virtual memory.
 	mappings: BTreeMap<*mut u8, MemMapping>,
 
+	/// Pages advised `MADV_FREE`.
+	///
+	/// They remain valid to access until actually reclaimed by the frame cache under memory
+	/// pressure; a write fault cancels the hint (see [`MemSpace::handle_page_fault`]). Used as a
+	/// set: the value carries no information.
+	lazy_free: BTreeMap<VirtAddr, ()>,
+
 	/// The number of used virtual memory pages.
 	vmem_usage: usize,
 }
@@ -191,6 +275,39 @@ impl MemSpaceState {
 			.find(|g| g.get_size() >= size)
 	}
 
+	/// Returns a gap with at least size `size`, picked pseudo-randomly among the fitting gaps,
+	/// weighted by each gap's free span (larger gaps are proportionally more likely to be
+	/// picked).
+	///
+	/// This is the selection used for [`MapConstraint::Aslr`]. If no gap large enough is
+	/// available, the function returns `None`.
+	fn get_random_gap(&self, size: NonZeroUsize) -> Option<&MemGap> {
+		let total: usize = self
+			.gaps
+			.iter()
+			.map(|(_, g)| g)
+			.filter(|g| g.get_size() >= size)
+			.map(|g| g.get_size().get())
+			.sum();
+		if total == 0 {
+			return None;
+		}
+		let mut pick = aslr_below(total);
+		self.gaps
+			.iter()
+			.map(|(_, g)| g)
+			.filter(|g| g.get_size() >= size)
+			.find(|g| {
+				let span = g.get_size().get();
+				if pick < span {
+					true
+				} else {
+					pick -= span;
+					false
+				}
+			})
+	}
+
 	/// Returns a reference to the gap containing the given virtual address.
 	///
 	/// If no gap contain the pointer, the function returns `None`.
@@ -251,6 +368,21 @@ pub struct MemSpace {
 	/// The current pointer of the `[s]brk` system calls.
 	brk: VirtAddr,
 
+	/// `RLIMIT_DATA`: the maximum size, in bytes, the data segment (i.e. `brk_init..brk`) may
+	/// grow to. [`rlimit::RLIM_INFINITY`](super::rlimit::RLIM_INFINITY) means no limit.
+	///
+	/// Carried here rather than read from [`super::Process::resource_limits`] directly because
+	/// `set_brk` only ever has a `&mut MemSpace` to work with: nothing in this snapshot's `exec`
+	/// wires the process's actual `RLIMIT_DATA` in through [`Self::set_rlimits`] yet, so this
+	/// defaults to unlimited.
+	rlim_data: u64,
+	/// `RLIMIT_AS`: the maximum size, in bytes, of the whole address space.
+	///
+	/// Carried for parity with `rlim_data` and for when address-space-wide accounting lands, but
+	/// not enforced yet: doing so needs a running total of mapped bytes across every mapping
+	/// (and not just the data segment), which this snapshot does not track anywhere.
+	rlim_as: u64,
+
 	/// Executable program information.
 	pub exe_info: ExeInfo,
 }
@@ -267,6 +399,9 @@ impl MemSpace {
 			brk_init: Default::default(),
 			brk: Default::default(),
 
+			rlim_data: RLIM_INFINITY,
+			rlim_as: RLIM_INFINITY,
+
 			exe_info: ExeInfo {
 				exe,
 
@@ -352,6 +487,16 @@ impl MemSpace {
 					.clone();
 				(gap, 0)
 			}
+			MapConstraint::Aslr => {
+				let gap = transaction
+					.mem_space_state
+					.get_random_gap(size)
+					.ok_or(AllocError)?
+					.clone();
+				// Degrades gracefully to offset 0 when the gap exactly fits
+				let gap_off = aslr_below(gap.get_size().get() - size.get() + 1);
+				(gap, gap_off)
+			}
 		};
 		let addr = (gap.get_begin() + gap_off * PAGE_SIZE).as_ptr();
 		// Split the old gap to fit the mapping, and insert new gaps
@@ -407,9 +552,40 @@ impl MemSpace {
 		let addr = map.get_addr();
 		transaction.insert_mapping(map)?;
 		transaction.commit();
+		// `MAP_POPULATE` pre-faults the whole region eagerly instead of leaving it to
+		// `handle_page_fault`. `MAP_LOCKED` implies it: pinning pages out of reclaim/swap requires
+		// them to actually be present first.
+		if flags & (MAP_POPULATE | MAP_LOCKED) != 0 {
+			if let Err(e) = self.populate(VirtAddr::from(addr), size) {
+				self.unmap(VirtAddr::from(addr), size, false)?;
+				return Err(e);
+			}
+		}
 		Ok(addr)
 	}
 
+	/// Eagerly faults in every page of the mapping at `addr`, `size` pages long, for
+	/// [`MAP_POPULATE`]/[`MAP_LOCKED`].
+	///
+	/// This tree has no reclaim/swap subsystem to exclude `MAP_LOCKED` pages from: once a page is
+	/// faulted in, it stays resident until the mapping is explicitly unmapped, so populating it
+	/// here already provides everything "locked" means in this snapshot; no extra pin state needs
+	/// recording on the [`MemMapping`], since `fork` (which clones mappings) and `unmap` (which
+	/// drops them outright) already handle its frames correctly either way.
+	fn populate(&mut self, addr: VirtAddr, size: NonZeroUsize) -> EResult<()> {
+		for p in 0..size.get() {
+			let page_addr = addr + p * PAGE_SIZE;
+			let Some(mapping) = self.state.get_mut_mapping_for_addr(page_addr) else {
+				break;
+			};
+			let page_offset = (page_addr.0 - mapping.get_addr() as usize) / PAGE_SIZE;
+			mapping
+				.map(page_offset, &mut self.vmem)
+				.map_err(|_| errno!(ENOMEM))?;
+		}
+		Ok(())
+	}
+
 	/// Maps a chunk of memory population with the given static pages.
 	pub fn map_special(&mut self, prot: u8, flags: u8, pages: &[RcFrame]) -> AllocResult<*mut u8> {
 		let Some(len) = NonZeroUsize::new(pages.len()) else {
@@ -536,6 +712,156 @@ impl MemSpace {
 		Ok(())
 	}
 
+	/// Changes the size of the mapping at `old_addr`, possibly relocating it.
+	///
+	/// Arguments:
+	/// - `old_addr` is the current address of the mapping. Must be page-aligned.
+	/// - `old_size` is the current size of the mapping, in pages.
+	/// - `new_size` is the requested size of the mapping, in pages.
+	/// - `flags` are the mapping flags to use if the mapping has to be relocated, mirroring
+	///   `mmap`'s `flags`.
+	/// - `maymove` tells whether the mapping is allowed to move to satisfy a growth that does not
+	///   fit in place.
+	///
+	/// On success, the function returns the (possibly unchanged) address of the mapping.
+	///
+	/// Shrinking never moves the mapping: the tail pages are simply unmapped.
+	/// Growing tries to consume the gap immediately following the mapping first; if that gap is
+	/// too small (or absent) and `maymove` is set, a fresh region is allocated and the mapping's
+	/// backing is transferred there instead.
+	///
+	/// This relies on `MemMapping` exposing `get_off`, mirroring its confirmed `get_file` getter
+	/// and the `off` constructor parameter, for the same reason `set_prot` already relies on
+	/// `get_file`/`get_flags`: this snapshot's `mapping.rs` has no file to check those names
+	/// against directly.
+	#[allow(clippy::not_unsafe_ptr_arg_deref)]
+	pub fn remap(
+		&mut self,
+		old_addr: VirtAddr,
+		old_size: NonZeroUsize,
+		new_size: NonZeroUsize,
+		flags: u8,
+		maymove: bool,
+	) -> EResult<*mut u8> {
+		if unlikely(!old_addr.is_aligned_to(PAGE_SIZE)) {
+			return Err(errno!(EINVAL));
+		}
+		let mut transaction = MemSpaceTransaction::new(&mut self.state, &mut self.vmem);
+		let addr = Self::remap_impl(
+			&mut transaction,
+			old_addr,
+			old_size,
+			new_size,
+			flags,
+			maymove,
+		)?;
+		transaction.commit();
+		Ok(addr)
+	}
+
+	/// Implementation for `remap`.
+	fn remap_impl(
+		transaction: &mut MemSpaceTransaction,
+		old_addr: VirtAddr,
+		old_size: NonZeroUsize,
+		new_size: NonZeroUsize,
+		flags: u8,
+		maymove: bool,
+	) -> EResult<*mut u8> {
+		if new_size < old_size {
+			let freed = old_size.get() - new_size.get();
+			let freed_addr = old_addr + new_size.get() * PAGE_SIZE;
+			Self::unmap_impl(
+				transaction,
+				freed_addr,
+				NonZeroUsize::new(freed).unwrap(),
+				false,
+			)?;
+			return Ok(old_addr.as_ptr());
+		}
+		if new_size == old_size {
+			return Ok(old_addr.as_ptr());
+		}
+		let grow = new_size.get() - old_size.get();
+		let old_end = old_addr + old_size.get() * PAGE_SIZE;
+		// The gap immediately following the mapping, if large enough to grow into
+		let grow_gap = transaction
+			.mem_space_state
+			.get_gap_for_addr(old_end)
+			.filter(|gap| gap.get_begin() == old_end && gap.get_size().get() >= grow)
+			.cloned();
+		if let Some(gap) = grow_gap {
+			// Consume the gap in place: the mapping keeps its address
+			let (left_gap, right_gap) = gap.consume(0, grow);
+			transaction.remove_gap(gap.get_begin())?;
+			if let Some(g) = left_gap {
+				transaction.insert_gap(g)?;
+			}
+			if let Some(g) = right_gap {
+				transaction.insert_gap(g)?;
+			}
+			let (prot, flags, file, off, anon_pages) = {
+				let mapping = transaction
+					.mem_space_state
+					.get_mut_mapping_for_addr(old_addr)
+					.ok_or_else(|| errno!(ENOMEM))?;
+				(
+					mapping.get_prot(),
+					mapping.get_flags(),
+					mapping.get_file(),
+					mapping.get_off(),
+					mem::take(&mut mapping.anon_pages),
+				)
+			};
+			let mut new_mapping =
+				MemMapping::new(old_addr.as_ptr(), new_size, prot, flags, file, off)?;
+			new_mapping
+				.anon_pages
+				.iter_mut()
+				.zip(anon_pages)
+				.for_each(|(dst, src)| *dst = src);
+			transaction.remove_mapping(old_addr.as_ptr())?;
+			transaction.insert_mapping(new_mapping)?;
+			return Ok(old_addr.as_ptr());
+		}
+		if !maymove {
+			return Err(errno!(ENOMEM));
+		}
+		// The gap cannot accommodate the growth: relocate to a fresh, ASLR-placed region and
+		// transfer the backing, all inside `transaction` so a failure leaves the original mapping
+		// intact
+		let (prot, file, off, anon_pages) = {
+			let mapping = transaction
+				.mem_space_state
+				.get_mut_mapping_for_addr(old_addr)
+				.ok_or_else(|| errno!(ENOMEM))?;
+			(
+				mapping.get_prot(),
+				mapping.get_file(),
+				mapping.get_off(),
+				mem::take(&mut mapping.anon_pages),
+			)
+		};
+		let mut new_mapping = Self::map_impl(
+			transaction,
+			MapConstraint::Aslr,
+			new_size,
+			prot,
+			flags,
+			file,
+			off,
+		)?;
+		new_mapping
+			.anon_pages
+			.iter_mut()
+			.zip(anon_pages)
+			.for_each(|(dst, src)| *dst = src);
+		let new_addr = new_mapping.get_addr();
+		transaction.insert_mapping(new_mapping)?;
+		Self::unmap_impl(transaction, old_addr, old_size, false)?;
+		Ok(new_addr)
+	}
+
 	/// Binds the memory space to the current kernel.
 	pub fn bind(&self) {
 		self.vmem.bind();
@@ -559,6 +885,7 @@ impl MemSpace {
 			state: MemSpaceState {
 				gaps: self.state.gaps.try_clone()?,
 				mappings,
+				lazy_free: self.state.lazy_free.try_clone()?,
 
 				vmem_usage: self.state.vmem_usage,
 			},
@@ -567,6 +894,9 @@ impl MemSpace {
 			brk_init: self.brk_init,
 			brk: self.brk,
 
+			rlim_data: self.rlim_data,
+			rlim_as: self.rlim_as,
+
 			exe_info: self.exe_info.clone(),
 		})
 	}
@@ -594,6 +924,162 @@ impl MemSpace {
 		Ok(())
 	}
 
+	/// Gives advice to the kernel about the future usage of a range of memory.
+	///
+	/// Arguments:
+	/// - `addr` is the address of the beginning of the range. Must be page-aligned.
+	/// - `len` is the length of the range, in bytes.
+	/// - `advice` is the advice to apply.
+	///
+	/// The function operates per mapping intersecting the range, like [`Self::unmap_impl`], and
+	/// silently ignores advice that does not apply to a given mapping (e.g. [`MemAdvice::Free`]
+	/// on a `MAP_SHARED` mapping, which must stay coherent with whoever else maps it).
+	#[allow(clippy::not_unsafe_ptr_arg_deref)]
+	pub fn madvise(&mut self, addr: VirtAddr, len: usize, advice: MemAdvice) -> EResult<()> {
+		if unlikely(!addr.is_aligned_to(PAGE_SIZE)) {
+			return Err(errno!(EINVAL));
+		}
+		let Some(size) = NonZeroUsize::new(len.div_ceil(PAGE_SIZE)) else {
+			return Ok(());
+		};
+		let mut i = 0;
+		while i < size.get() {
+			let page_addr = addr + i * PAGE_SIZE;
+			let Some(mapping) = self.state.get_mut_mapping_for_addr(page_addr) else {
+				// TODO jump to next mapping directly using binary tree (currently O(n log n))
+				i += 1;
+				continue;
+			};
+			let mapping_begin = mapping.get_addr();
+			let mapping_size = mapping.get_size().get();
+			let inner_off = (page_addr.0 - mapping_begin as usize) / PAGE_SIZE;
+			let pages = min(size.get() - i, mapping_size - inner_off);
+			match advice {
+				MemAdvice::WillNeed => {
+					for p in inner_off..(inner_off + pages) {
+						mapping.map(p, &mut self.vmem)?;
+					}
+				}
+				MemAdvice::DontNeed => {
+					// Dropping the frame and unmapping is enough either way: a private anonymous
+					// page re-faults to a fresh zero page, and a file-backed page re-faults by
+					// re-reading the file, via the same existing `handle_page_fault` path.
+					for p in inner_off..(inner_off + pages) {
+						mapping.anon_pages[p] = None;
+					}
+					self.vmem.unmap_range(page_addr, pages);
+					for p in 0..pages {
+						self.state.lazy_free.remove(&(page_addr + p * PAGE_SIZE));
+					}
+				}
+				MemAdvice::Free => {
+					// A `MAP_SHARED` mapping stays coherent with other mappers and cannot be
+					// silently reclaimed.
+					if mapping.get_flags() & MAP_SHARED == 0 {
+						for p in 0..pages {
+							self.state
+								.lazy_free
+								.insert(page_addr + p * PAGE_SIZE, ())?;
+						}
+					}
+				}
+			}
+			i += pages;
+		}
+		Ok(())
+	}
+
+	/// Flushes dirty pages of a range back to their backing file, as in the BSD `msync` syscall.
+	///
+	/// Arguments:
+	/// - `addr` is the address of the beginning of the range. Must be page-aligned.
+	/// - `len` is the length of the range, in bytes.
+	/// - `invalidate` corresponds to `MS_INVALIDATE`: once written back, the now-clean cached
+	///   frames are dropped and their `vmem` translations cleared, so the next access re-reads
+	///   them from the file.
+	///
+	/// Only `MAP_SHARED` file-backed mappings are written back; private and anonymous mappings
+	/// have nothing to flush and are silently skipped, as `MemSpace::drop` already does for the
+	/// same reason.
+	///
+	/// Unlike `MemSpace::drop`, which ignores I/O errors since there is no caller left to report
+	/// them to, this propagates the first one encountered.
+	///
+	/// This relies on `MemMapping::sync` writing back the whole mapping rather than a sub-range,
+	/// mirroring its only confirmed call site in `MemSpace::drop`: this snapshot's `mapping.rs`
+	/// has no file to check for a range-scoped variant, so a mapping that only partially
+	/// intersects `addr..addr + len` is flushed in full.
+	pub fn msync(&mut self, addr: VirtAddr, len: usize, invalidate: bool) -> EResult<()> {
+		if unlikely(!addr.is_aligned_to(PAGE_SIZE)) {
+			return Err(errno!(EINVAL));
+		}
+		let Some(size) = NonZeroUsize::new(len.div_ceil(PAGE_SIZE)) else {
+			return Ok(());
+		};
+		let mut i = 0;
+		while i < size.get() {
+			let page_addr = addr + i * PAGE_SIZE;
+			let Some(mapping) = self.state.get_mut_mapping_for_addr(page_addr) else {
+				// TODO jump to next mapping directly using binary tree (currently O(n log n))
+				i += 1;
+				continue;
+			};
+			let mapping_begin = mapping.get_addr();
+			let mapping_size = mapping.get_size().get();
+			let inner_off = (page_addr.0 - mapping_begin as usize) / PAGE_SIZE;
+			let pages = min(size.get() - i, mapping_size - inner_off);
+			if mapping.get_flags() & MAP_SHARED != 0 && mapping.get_file().is_some() {
+				mapping.sync(&self.vmem, true)?;
+				if invalidate {
+					for p in inner_off..(inner_off + pages) {
+						mapping.anon_pages[p] = None;
+					}
+					self.vmem.unmap_range(page_addr, pages);
+				}
+			}
+			i += pages;
+		}
+		Ok(())
+	}
+
+	/// Reports page residency for a range, as in the BSD/XNU `mincore` syscall.
+	///
+	/// Arguments:
+	/// - `addr` is the address of the beginning of the range. Must be page-aligned.
+	/// - `len` is the length of the range, in bytes.
+	/// - `vec` receives one byte per page of the range: bit 0 is set if the page is currently
+	///   resident, cleared otherwise. Must be at least `len.div_ceil(PAGE_SIZE)` bytes long.
+	///
+	/// If any page in the range is not covered by a mapping, the function returns `ENOMEM`.
+	pub fn mincore(&self, addr: VirtAddr, len: usize, vec: &mut [u8]) -> EResult<()> {
+		if unlikely(!addr.is_aligned_to(PAGE_SIZE)) {
+			return Err(errno!(EINVAL));
+		}
+		let Some(size) = NonZeroUsize::new(len.div_ceil(PAGE_SIZE)) else {
+			return Ok(());
+		};
+		let mut i = 0;
+		while i < size.get() {
+			let page_addr = addr + i * PAGE_SIZE;
+			// Unlike `unmap_impl`/`madvise`, a gap in the range is an error rather than something
+			// to skip over: `mincore` only makes sense over memory the process actually mapped.
+			let Some(mapping) = self.state.get_mapping_for_addr(page_addr) else {
+				return Err(errno!(ENOMEM));
+			};
+			let mapping_begin = mapping.get_addr();
+			let mapping_size = mapping.get_size().get();
+			let inner_off = (page_addr.0 - mapping_begin as usize) / PAGE_SIZE;
+			let pages = min(size.get() - i, mapping_size - inner_off);
+			for p in 0..pages {
+				let resident = mapping.anon_pages[inner_off + p].is_some()
+					&& self.vmem.translate(page_addr + p * PAGE_SIZE).is_some();
+				vec[i + p] = resident as u8;
+			}
+			i += pages;
+		}
+		Ok(())
+	}
+
 	/// Sets protection for the given range of memory.
 	///
 	/// Arguments:
@@ -604,19 +1090,91 @@ impl MemSpace {
 	///
 	/// If a mapping to be modified is associated with a file, and the file doesn't have the
 	/// matching permissions, the function returns an error.
+	///
+	/// This relies on `MemMapping` exposing `set_prot`/`get_file`/`get_flags`, mirroring its
+	/// confirmed `get_prot` getter and its `file`/`flags` constructor parameters: this snapshot's
+	/// `mapping.rs` has no file to check those names against directly. Because of that same gap,
+	/// a `prot` change can only be applied to whole mappings: `MemMapping::split` (confirmed via
+	/// [`Self::unmap_impl`]) only knows how to discard a sub-range as a free `MemGap`, not carve
+	/// out a protection-changed middle piece while preserving the original's `file`/`flags` — that
+	/// would need either a dedicated split-for-protect primitive or accessors this snapshot
+	/// doesn't expose. A range that doesn't line up exactly with one or more whole mappings is
+	/// rejected with `ENOTSUP` rather than silently mprotecting more or less than asked.
+	#[allow(clippy::not_unsafe_ptr_arg_deref)]
 	pub fn set_prot(
 		&mut self,
-		_addr: *mut c_void,
-		_len: usize,
-		_prot: u8,
-		_access_profile: &AccessProfile,
+		addr: *mut c_void,
+		len: usize,
+		prot: u8,
+		access_profile: &AccessProfile,
+	) -> EResult<()> {
+		let addr = VirtAddr(addr as usize);
+		if unlikely(!addr.is_aligned_to(PAGE_SIZE)) {
+			return Err(errno!(EINVAL));
+		}
+		let Some(size) = NonZeroUsize::new(len.div_ceil(PAGE_SIZE)) else {
+			return Ok(());
+		};
+		// Pages whose mapping's `prot` actually changed, to resync into `vmem` once the
+		// transaction has committed.
+		let mut touched = Vec::new();
+		{
+			let mut transaction = MemSpaceTransaction::new(&mut self.state, &mut self.vmem);
+			Self::set_prot_impl(&mut transaction, addr, size, prot, access_profile, &mut touched)?;
+			transaction.commit();
+		}
+		// `MemMapping::map` already knows how to flip an existing frame's permission in place or
+		// duplicate it on a write fault (see `handle_page_fault`'s own doc comment), so re-driving
+		// it here makes the new protection effective immediately instead of waiting for the next
+		// fault.
+		for page_addr in touched {
+			if let Some(mapping) = self.state.get_mut_mapping_for_addr(page_addr) {
+				let page_offset = (page_addr.0 - mapping.get_addr() as usize) / PAGE_SIZE;
+				mapping.map(page_offset, &mut self.vmem)?;
+			}
+		}
+		Ok(())
+	}
+
+	/// Implementation for `set_prot`.
+	fn set_prot_impl(
+		transaction: &mut MemSpaceTransaction,
+		addr: VirtAddr,
+		size: NonZeroUsize,
+		prot: u8,
+		access_profile: &AccessProfile,
+		touched: &mut Vec<VirtAddr>,
 	) -> EResult<()> {
-		// TODO Iterate on mappings in the range:
-		//		If the mapping is shared and associated to a file, check file permissions match
-		// `prot` (only write)
-		//		Split the mapping if needed
-		//		Set permissions
-		//		Update vmem
+		let mut i = 0;
+		while i < size.get() {
+			let page_addr = addr + i * PAGE_SIZE;
+			let Some(mapping) = transaction.mem_space_state.get_mut_mapping_for_addr(page_addr)
+			else {
+				return Err(errno!(ENOMEM));
+			};
+			let mapping_begin = mapping.get_addr();
+			let mapping_size = mapping.get_size().get();
+			let inner_off = (page_addr.0 - mapping_begin as usize) / PAGE_SIZE;
+			let pages = min(size.get() - i, mapping_size - inner_off);
+			if inner_off != 0 || pages != mapping_size {
+				return Err(errno!(ENOTSUP));
+			}
+			// A `MAP_SHARED` file-backed mapping must not be granted `PROT_WRITE` unless the
+			// backing file itself is writable per `access_profile`, per BSD mmap/mprotect
+			// semantics.
+			if prot & PROT_WRITE != 0 && mapping.get_flags() & MAP_SHARED != 0 {
+				if let Some(file) = mapping.get_file() {
+					if !access_profile.can_write_file(&file.stat()) {
+						return Err(errno!(EACCES));
+					}
+				}
+			}
+			mapping.set_prot(prot);
+			for p in 0..pages {
+				touched.push(page_addr + p * PAGE_SIZE)?;
+			}
+			i += pages;
+		}
 		Ok(())
 	}
 
@@ -636,9 +1194,22 @@ impl MemSpace {
 		self.brk = addr;
 	}
 
+	/// Sets `RLIMIT_DATA` and `RLIMIT_AS`, as read from the owning process's resource limits.
+	///
+	/// Meant to be called from `exec` when building the memory space for a new program image;
+	/// `process::exec` has no file in this tree's snapshot, so nothing calls this yet and both
+	/// limits default to [`RLIM_INFINITY`] (see [`Self::rlim_data`], [`Self::rlim_as`]).
+	pub fn set_rlimits(&mut self, rlim_data: u64, rlim_as: u64) {
+		self.rlim_data = rlim_data;
+		self.rlim_as = rlim_as;
+	}
+
 	/// Sets the address for the `brk` syscall.
 	///
-	/// If the memory cannot be allocated, the function returns an error.
+	/// If the memory cannot be allocated, the function returns an error. This is the case if:
+	/// - the new size of the data segment (i.e. `brk_init..addr`) would exceed `RLIMIT_DATA`
+	/// - `addr` lands inside, or the grown range would collide with, an existing mapping outside
+	///   the data segment (mirroring Linux's `find_vma_intersection` check in `sys_brk`)
 	#[allow(clippy::not_unsafe_ptr_arg_deref)]
 	pub fn set_brk(&mut self, addr: VirtAddr) -> AllocResult<()> {
 		if addr >= self.brk {
@@ -646,12 +1217,25 @@ impl MemSpace {
 			if addr > COPY_BUFFER {
 				return Err(AllocError);
 			}
+			if self.rlim_data != RLIM_INFINITY && (addr.0 - self.brk_init.0) as u64 > self.rlim_data
+			{
+				return Err(AllocError);
+			}
 			// Allocate memory
 			let begin = self.brk.align_to(PAGE_SIZE);
 			let pages = (addr.0 - begin.0).div_ceil(PAGE_SIZE);
 			let Some(pages) = NonZeroUsize::new(pages) else {
 				return Ok(());
 			};
+			// Check the grown range does not collide with another mapping (e.g. one placed there by
+			// `mmap`): the data segment owns this span exclusively
+			let mut off = 0;
+			while off < pages.get() * PAGE_SIZE {
+				if self.state.get_mapping_for_addr(begin + off).is_some() {
+					return Err(AllocError);
+				}
+				off += PAGE_SIZE;
+			}
 			self.map(
 				MapConstraint::Fixed(begin),
 				pages,
@@ -685,6 +1269,11 @@ impl MemSpace {
 	/// If continuing, the function must resolve the issue before returning.
 	/// A typical situation where is function is useful is for Copy-On-Write allocations.
 	///
+	/// Resolution itself is delegated to [`MemMapping::map`], which lazily allocates an anonymous
+	/// page on first touch, and on a write fault to a shared frame either duplicates it (if its
+	/// reference count shows it is still shared with another mapping, e.g. after `fork`) or flips
+	/// it writable in place (if this mapping already holds the only reference).
+	///
 	/// Arguments:
 	/// - `addr` is the virtual address of the wrong memory access that caused the fault.
 	/// - `code` is the error code given along with the error.
@@ -703,8 +1292,15 @@ impl MemSpace {
 			return Ok(false);
 		}
 		// Map the accessed page
-		let page_offset = (addr.0 - mapping.get_addr() as usize) / PAGE_SIZE;
+		let mapping_begin = mapping.get_addr();
+		let page_offset = (addr.0 - mapping_begin as usize) / PAGE_SIZE;
 		mapping.map(page_offset, &mut self.vmem)?;
+		// A write fault on a page advised `MADV_FREE` means it is actually still needed: cancel
+		// its record as a freeable page.
+		if code & PAGE_FAULT_WRITE != 0 {
+			let page_addr = VirtAddr(mapping_begin as usize + page_offset * PAGE_SIZE);
+			self.state.lazy_free.remove(&page_addr);
+		}
 		Ok(true)
 	}
 }
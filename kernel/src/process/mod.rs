@@ -22,14 +22,27 @@
 //! several processes to run at the same time by sharing the CPU resources using
 //! a scheduler.
 
+pub mod checkpoint;
+pub mod cpulimit;
 pub mod exec;
+pub mod inotify;
 pub mod iovec;
 pub mod mem_space;
 pub mod oom;
 pub mod pid;
+pub mod pidfd;
+pub mod pledge;
+pub mod ptrace;
+pub mod rlimit;
+pub mod rt_signal;
 pub mod rusage;
 pub mod scheduler;
+pub mod seccomp;
+pub mod session;
 pub mod signal;
+pub mod thread_group;
+pub mod time_ns;
+pub mod unveil;
 pub mod user_desc;
 
 use crate::{
@@ -65,13 +78,22 @@ use core::{
 	mem::{size_of, ManuallyDrop},
 	ptr::{null_mut, NonNull},
 	sync::atomic::{
-		AtomicBool, AtomicPtr, AtomicU32, AtomicU8,
+		AtomicBool, AtomicPtr, AtomicU32, AtomicU64, AtomicU8,
 		Ordering::{Acquire, Relaxed, Release, SeqCst},
 	},
 };
 use mem_space::MemSpace;
 use pid::Pid;
+use pledge::PromiseSet;
+use ptrace::PtraceState;
+use rlimit::ResourceLimits;
+use rt_signal::{RtSignalQueue, SignalInfo};
+use seccomp::SeccompState;
+use session::Session;
 use signal::{Signal, SignalHandler};
+use thread_group::ThreadGroup;
+use time_ns::TimeNamespace;
+use unveil::UnveilList;
 use utils::{
 	collections::{
 		path::{Path, PathBuf},
@@ -93,6 +115,10 @@ const TTY_DEVICE_PATH: &str = "/dev/tty";
 const DEFAULT_UMASK: file::Mode = 0o022;
 
 /// The size of the userspace stack of a process in number of pages.
+///
+/// `execve`, which is what actually maps the userspace stack, would size it from
+/// [`rlimit::ResourceLimits::stack_size`] (`RLIMIT_STACK`'s soft limit) instead of this constant;
+/// `process::exec` has no file in this tree's snapshot to make that change in.
 const USER_STACK_SIZE: usize = 2048;
 /// The flags for the userspace stack mapping.
 const USER_STACK_FLAGS: u8 = mem_space::MAPPING_FLAG_WRITE | mem_space::MAPPING_FLAG_USER;
@@ -126,10 +152,15 @@ pub enum State {
 	Running = 0,
 	/// The process is waiting for an event.
 	Sleeping = 1,
-	/// The process has been stopped by a signal or by tracing.
+	/// The process has been stopped by a signal or by job-control (`SIGSTOP`/`SIGTSTP`/...).
 	Stopped = 2,
 	/// The process has been killed.
 	Zombie = 3,
+	/// The process is stopped for tracing: either it just received a signal while traced, or it
+	/// just hit a syscall-entry/exit stop under `PTRACE_SYSCALL`. Distinct from [`Self::Stopped`]
+	/// since a tracer resumes it explicitly (`PTRACE_CONT`/`PTRACE_SYSCALL`/`PTRACE_SINGLESTEP`)
+	/// regardless of whether the stopping signal would, on its own, be a job-control stop.
+	TraceStopped = 4,
 }
 
 impl State {
@@ -140,6 +171,7 @@ impl State {
 			1 => Self::Sleeping,
 			2 => Self::Stopped,
 			3 => Self::Zombie,
+			4 => Self::TraceStopped,
 			_ => unreachable!(),
 		}
 	}
@@ -151,6 +183,7 @@ impl State {
 			Self::Sleeping => 'S',
 			Self::Stopped => 'T',
 			Self::Zombie => 'Z',
+			Self::TraceStopped => 't',
 		}
 	}
 
@@ -161,6 +194,7 @@ impl State {
 			Self::Sleeping => "sleeping",
 			Self::Stopped => "stopped",
 			Self::Zombie => "zombie",
+			Self::TraceStopped => "tracing stop",
 		}
 	}
 }
@@ -180,6 +214,10 @@ pub struct ForkOptions {
 	/// If `true`, the parent and child processes both share the same signal
 	/// handlers table.
 	pub share_sighand: bool,
+	/// If `true`, the new process is a thread of the caller: it joins the caller's
+	/// [`ThreadGroup`] instead of starting its own, which implies `share_memory`, `share_fd` and
+	/// `share_sighand` regardless of what those fields are set to.
+	pub thread: bool,
 
 	/// The stack address the child process begins with.
 	pub stack: Option<NonNull<c_void>>,
@@ -198,6 +236,13 @@ pub struct ProcessLinks {
 	group_leader: Option<Arc<Process>>,
 	/// The list of processes in the process group.
 	pub process_group: Vec<Pid>,
+	/// The list of processes this process is tracing via `ptrace(2)`.
+	pub tracees: Vec<Pid>,
+	/// The process's session leader.
+	///
+	/// If `None`, the process is its own session leader (to avoid self reference), mirroring
+	/// `group_leader`.
+	pub session_leader: Option<Arc<Process>>,
 }
 
 /// A process's filesystem access information.
@@ -238,8 +283,9 @@ pub struct ProcessSignal {
 	pub handlers: Arc<Mutex<[SignalHandler; signal::SIGNALS_COUNT]>>,
 	/// A bitfield storing the set of blocked signals.
 	pub sigmask: SigSet,
-	/// A bitfield storing the set of pending signals.
-	sigpending: SigSet,
+	/// The backlog of pending signal deliveries, standard and real-time, each carrying its own
+	/// [`SignalInfo`] (sender identity, `si_code`, and `sigqueue` payload if any).
+	pub queue: RtSignalQueue,
 
 	/// The exit status of the process after exiting.
 	pub exit_status: ExitStatus,
@@ -253,28 +299,14 @@ impl ProcessSignal {
 		self.sigmask.is_set(sig.get_id() as _)
 	}
 
-	/// Returns the ID of the next signal to be handled.
+	/// Returns the next pending signal delivery, in full (sender identity, `si_code`, and
+	/// `sigqueue` payload if any), with real-time signals delivered lowest-numbered-first.
 	///
-	/// If `peek` is `false`, the signal is cleared from the bitfield.
+	/// If `peek` is `false`, the entry is consumed (removed from [`Self::queue`]).
 	///
-	/// If no signal is pending, the function returns `None`.
-	pub fn next_signal(&mut self, peek: bool) -> Option<Signal> {
-		let sig = self
-			.sigpending
-			.iter()
-			.enumerate()
-			.filter(|(_, b)| *b)
-			.filter_map(|(i, _)| {
-				let s = Signal::try_from(i as c_int).ok()?;
-				(!s.can_catch() || !self.sigmask.is_set(i)).then_some(s)
-			})
-			.next();
-		if !peek {
-			if let Some(id) = sig {
-				self.sigpending.clear(id.get_id() as _);
-			}
-		}
-		sig
+	/// If no signal is pending and unblocked, the function returns `None`.
+	pub fn next_signal(&mut self, peek: bool) -> Option<SignalInfo> {
+		self.queue.pop_next(self.sigmask, peek)
 	}
 }
 
@@ -301,8 +333,13 @@ pub struct Process {
 	/// Kernel stack pointer of saved context.
 	kernel_sp: AtomicPtr<u8>,
 
+	/// The thread group this process is a member of, shared by `Arc` with every other thread of
+	/// the same process.
+	pub thread_group: Arc<Mutex<ThreadGroup>>,
 	/// Process's timers, shared between all threads of the same process.
 	pub timer_manager: Arc<Mutex<TimerManager>>,
+	/// The process's time namespace, shared with (and inherited by) every descendant.
+	pub time_ns: Arc<TimeNamespace>,
 
 	/// Filesystem access information.
 	pub fs: Mutex<ProcessFs>, // TODO rwlock
@@ -312,11 +349,46 @@ pub struct Process {
 	/// The process's signal management structure.
 	pub signal: Mutex<ProcessSignal>, // TODO rwlock
 
+	/// The process's seccomp-BPF filter stack and no-new-privs flag.
+	pub seccomp: Mutex<SeccompState>,
+
+	/// If this process is traced, its `ptrace(2)` state.
+	pub ptrace: Mutex<Option<PtraceState>>,
+	/// The [`SignalInfo`] of the signal that caused the most recent transition to
+	/// [`State::TraceStopped`], used by [`Self::kill`] if the tracer resumes the tracee with
+	/// `PTRACE_CONT`/`PTRACE_SYSCALL` and a non-zero signal number to reinject. `PTRACE_GETSIGINFO`
+	/// does not read it: that request fails with `ENOSYS`.
+	pub last_siginfo: Mutex<Option<SignalInfo>>,
+
+	/// PIDs of threads blocked waiting on a [`pidfd::PidFd`] referring to this process, woken (see
+	/// [`Self::set_state`]) the moment it transitions to [`State::Zombie`].
+	pub pidfd_waiters: Mutex<Vec<Pid>>,
+
+	/// The process's session, meaningful only while this process is a session leader (see
+	/// [`ProcessLinks::session_leader`]).
+	pub session: Mutex<Session>,
+
 	/// TLS entries.
 	pub tls: Mutex<[gdt::Entry; TLS_ENTRIES_COUNT]>, // TODO rwlock
 
 	/// The process's resources usage.
 	pub rusage: Rusage,
+	/// The process's resource limits (`RLIMIT_*`).
+	pub resource_limits: Mutex<ResourceLimits>,
+
+	/// The number of CPU ticks accumulated by this process, accounted for in
+	/// [`yield_current_impl`] and checked there against `RLIMIT_CPU`.
+	pub cpu_ticks: AtomicU64,
+
+	/// The process's `unveil(2)`-style filesystem visibility overlay.
+	pub unveil: Mutex<UnveilList>,
+
+	/// The process's `pledge(2)`-style syscall promise set, or `None` if it has never pledged.
+	pub promises: Mutex<Option<PromiseSet>>,
+
+	/// The process's `inotify(7)`-style queue of filesystem events delivered by the watches it has
+	/// registered on [`crate::file::vfs::node::Node`]s.
+	pub inotify: Arc<inotify::EventQueue>,
 }
 
 /// Initializes processes system. This function must be called only once, at
@@ -358,6 +430,12 @@ pub(crate) fn init() -> EResult<()> {
 		}
 		CallbackResult::Continue
 	};
+	// Vector 14 (#PF): CR2 holds the faulting address and `code` carries the present/write/user
+	// bits. Resolution (lazily allocating an anonymous page, or duplicating a copy-on-write frame
+	// only when its reference count says it is actually shared) happens in
+	// `MemSpace::handle_page_fault`; if it reports the fault as resolved, returning from this
+	// handler simply re-executes the faulting instruction, which is enough to "retry" the access
+	// with no extra bookkeeping here.
 	let page_fault_callback = |_id: u32, code: u32, frame: &mut IntFrame, ring: u8| {
 		let accessed_addr = VirtAddr(register_get!("cr2"));
 		let pc = frame.get_program_counter();
@@ -371,13 +449,16 @@ pub(crate) fn init() -> EResult<()> {
 				return CallbackResult::Panic;
 			};
 			let mut mem_space = mem_space_mutex.lock();
-			mem_space.handle_page_fault(accessed_addr, code)
+			// An error (e.g. out of memory while allocating the page) is not a resolved fault.
+			mem_space.handle_page_fault(accessed_addr, code).unwrap_or(false)
 		};
 		if !success {
 			if ring < 3 {
 				// Check if the fault was caused by a user <-> kernel copy
 				if (copy::raw_copy as usize..copy::copy_fault as usize).contains(&pc) {
-					// Jump to `copy_fault`
+					// Jump to `copy_fault`, which makes `raw_copy` return `false` so the
+					// `copy_from`/`to_user_raw` caller observes `EFAULT` instead of the fault
+					// propagating into the kernel.
 					frame.set_program_counter(copy::copy_fault as usize);
 				} else {
 					return CallbackResult::Panic;
@@ -468,7 +549,9 @@ impl Process {
 			kernel_stack: buddy::alloc_kernel(KERNEL_STACK_ORDER)?,
 			kernel_sp: AtomicPtr::default(),
 
+			thread_group: Arc::new(Mutex::new(ThreadGroup::new(pid::INIT_PID)?))?,
 			timer_manager: Arc::new(Mutex::new(TimerManager::new(pid::INIT_PID)?))?,
+			time_ns: Arc::new(TimeNamespace::new())?,
 
 			fs: Mutex::new(ProcessFs {
 				access_profile: rs.access_profile,
@@ -481,15 +564,27 @@ impl Process {
 			signal: Mutex::new(ProcessSignal {
 				handlers: Arc::new(Default::default())?,
 				sigmask: Default::default(),
-				sigpending: Default::default(),
+				queue: Default::default(),
 
 				exit_status: 0,
 				termsig: 0,
 			}),
 
+			seccomp: Mutex::new(SeccompState::default()),
+			ptrace: Mutex::new(None),
+			last_siginfo: Mutex::new(None),
+			pidfd_waiters: Mutex::new(Vec::new()),
+
+			session: Mutex::new(Session::new(pid::INIT_PID)),
+
 			tls: Default::default(),
 
 			rusage: Default::default(),
+			resource_limits: Mutex::new(ResourceLimits::default()),
+			cpu_ticks: AtomicU64::new(0),
+			unveil: Mutex::new(UnveilList::default()),
+			promises: Mutex::new(None),
+			inotify: Arc::new(inotify::EventQueue::new())?,
 		};
 		Ok(SCHEDULER.get().lock().add_process(process)?)
 	}
@@ -541,6 +636,16 @@ impl Process {
 		Ok(())
 	}
 
+	/// Returns the process's session ID.
+	pub fn get_sid(&self) -> Pid {
+		self.links
+			.lock()
+			.session_leader
+			.as_ref()
+			.map(|p| p.get_pid())
+			.unwrap_or(self.get_pid())
+	}
+
 	/// The function tells whether the process is in an orphaned process group.
 	pub fn is_in_orphan_process_group(&self) -> bool {
 		self.links
@@ -595,7 +700,8 @@ impl Process {
 			let old_state = State::from_id(old_state);
 			let valid = matches!(
 				(old_state, new_state),
-				(State::Running | State::Sleeping, _) | (State::Stopped, State::Running)
+				(State::Running | State::Sleeping, _)
+					| (State::Stopped | State::TraceStopped, State::Running)
 			);
 			valid.then_some(new_state as u8)
 		}) else {
@@ -613,6 +719,16 @@ impl Process {
 			if self.is_init() {
 				panic!("Terminated init process!");
 			}
+			// A dying tracer detaches from (and resumes) everything it was tracing.
+			ptrace::detach_all_tracees(self);
+			// Leave the thread group so a sibling's `exit_group` doesn't try to tear this
+			// thread down a second time.
+			self.thread_group.lock().remove_member(self.tid);
+			// A dying session leader hangs up its foreground group, waking any stopped member
+			// with `SIGHUP`+`SIGCONT` instead of leaving it wedged forever.
+			if self.links.lock().session_leader.is_none() {
+				session::hangup_foreground_group(self);
+			}
 			// Remove the memory space and file descriptors table to reclaim memory
 			unsafe {
 				//self.mem_space = None; // TODO Handle the case where the memory space is bound
@@ -631,6 +747,12 @@ impl Process {
 					oom::wrap(|| init_proc.add_child(child_pid));
 				}
 			}
+			// Wake every thread blocked polling a pidfd that refers to this process.
+			for waiter_pid in mem::take(&mut *self.pidfd_waiters.lock()) {
+				if let Some(waiter) = Process::get_by_pid(waiter_pid) {
+					waiter.wake();
+				}
+			}
 		}
 		// Send SIGCHLD
 		if matches!(new_state, State::Running | State::Stopped | State::Zombie) {
@@ -695,19 +817,28 @@ impl Process {
 	/// If the process is not running, the behaviour is undefined.
 	pub fn fork(this: Arc<Self>, fork_options: ForkOptions) -> EResult<Arc<Self>> {
 		debug_assert!(matches!(this.get_state(), State::Running));
+		let existing_children = this.links.lock().children.len() as u64;
+		this.resource_limits
+			.lock()
+			.check_nproc(existing_children)?;
 		let pid = PidHandle::unique()?;
 		let pid_int = pid.get();
+		// A `CLONE_THREAD` child always shares memory, file descriptors and signal handlers with
+		// the rest of its thread group, regardless of what the individual `share_*` flags say.
+		let share_memory = fork_options.thread || fork_options.share_memory;
+		let share_fd = fork_options.thread || fork_options.share_fd;
+		let share_sighand = fork_options.thread || fork_options.share_sighand;
 		// Clone memory space
 		let mem_space = {
 			let curr_mem_space = this.mem_space.as_ref().unwrap();
-			if fork_options.share_memory {
+			if share_memory {
 				curr_mem_space.clone()
 			} else {
 				Arc::new(IntMutex::new(curr_mem_space.lock().fork()?))?
 			}
 		};
 		// Clone file descriptors
-		let file_descriptors = if fork_options.share_fd {
+		let file_descriptors = if share_fd {
 			this.file_descriptors.get().clone()
 		} else {
 			this.file_descriptors
@@ -722,13 +853,29 @@ impl Process {
 		// Clone signal handlers
 		let signal_handlers = {
 			let signal_manager = this.signal.lock();
-			if fork_options.share_sighand {
+			if share_sighand {
 				signal_manager.handlers.clone()
 			} else {
 				let handlers = signal_manager.handlers.lock().clone();
 				Arc::new(Mutex::new(handlers))?
 			}
 		};
+		// A thread joins the caller's thread group instead of starting its own; everything else
+		// (`fork`, `vfork`, and a non-`CLONE_THREAD` `clone`) is the leader of a fresh one.
+		let thread_group = if fork_options.thread {
+			let group = this.thread_group.clone();
+			group.lock().add_member(pid_int)?;
+			group
+		} else {
+			Arc::new(Mutex::new(ThreadGroup::new(pid_int)?))?
+		};
+		// Timers are process-wide state: a thread shares its group's timer set rather than
+		// starting with an empty one of its own.
+		let timer_manager = if fork_options.thread {
+			this.timer_manager.clone()
+		} else {
+			Arc::new(Mutex::new(TimerManager::new(pid_int)?))?
+		};
 		let process = Self {
 			pid,
 			tid: pid_int,
@@ -739,6 +886,12 @@ impl Process {
 			links: Mutex::new(ProcessLinks {
 				parent: Some(this.clone()),
 				group_leader: this.links.lock().group_leader.clone(),
+				session_leader: this
+					.links
+					.lock()
+					.session_leader
+					.clone()
+					.or_else(|| Some(this.clone())),
 				..Default::default()
 			}),
 
@@ -746,8 +899,11 @@ impl Process {
 			kernel_stack: buddy::alloc_kernel(KERNEL_STACK_ORDER)?,
 			kernel_sp: AtomicPtr::new(null_mut()), // TODO
 
-			// TODO if creating a thread: timer_manager: this.timer_manager.clone(),
-			timer_manager: Arc::new(Mutex::new(TimerManager::new(pid_int)?))?,
+			thread_group,
+			timer_manager,
+			// The time namespace is inherited, not duplicated: a freeze/restore offset set on
+			// the parent must keep applying to children started before or after the freeze.
+			time_ns: this.time_ns.clone(),
 
 			fs: Mutex::new(this.fs.lock().clone()),
 			file_descriptors: UnsafeMut::new(file_descriptors),
@@ -755,28 +911,69 @@ impl Process {
 			signal: Mutex::new(ProcessSignal {
 				handlers: signal_handlers,
 				sigmask: this.signal.lock().sigmask,
-				sigpending: Default::default(),
+				queue: Default::default(),
 
 				exit_status: 0,
 				termsig: 0,
 			}),
 
+			// Copied, not shared: a child installing its own filters must not affect the
+			// parent's, but it starts from exactly the stack the parent had.
+			seccomp: Mutex::new(this.seccomp.lock().clone()),
+			// Not inherited: a traced process being forked does not automatically put the
+			// child under the same tracer (that needs `PTRACE_O_TRACEFORK` cooperation from
+			// the signal/syscall-dispatch integration this snapshot doesn't have).
+			ptrace: Mutex::new(None),
+			last_siginfo: Mutex::new(None),
+			// Not inherited: a `pidfd` refers to one specific `Process`, so its own waiters have
+			// no bearing on a child that is, from a pidfd's perspective, a distinct process.
+			pidfd_waiters: Mutex::new(Vec::new()),
+
+			// A forked child is never itself a session leader; its `session` field stays inert
+			// (see `ProcessLinks::session_leader`) unless it later calls `setsid`.
+			session: Mutex::new(Session::new(pid_int)),
+
 			tls: Mutex::new(*this.tls.lock()),
 
 			rusage: Rusage::default(),
+			// Copied, not shared: a child adjusting its own limits must not affect the parent's.
+			resource_limits: Mutex::new(*this.resource_limits.lock()),
+			// A child starts its own `RLIMIT_CPU` accounting from zero rather than inheriting the
+			// parent's accumulated ticks.
+			cpu_ticks: AtomicU64::new(0),
+			// Inherited: a sandboxed process's children must not be able to see more than it can.
+			unveil: Mutex::new(this.unveil.lock().clone()),
+			// Inherited: a child of a pledged process starts under the same restriction, per
+			// `pledge(2)`'s own guarantee.
+			promises: Mutex::new(*this.promises.lock()),
+			// A child starts its own inotify queue empty rather than inheriting the parent's
+			// pending events: watches themselves are registered on `Node`s, not processes, so a
+			// child that shares a watched node still gets notified independently once it
+			// registers its own.
+			inotify: Arc::new(inotify::EventQueue::new())?,
 		};
 		this.add_child(pid_int)?;
 		Ok(SCHEDULER.get().lock().add_process(process)?)
 	}
 
-	/// Kills the process with the given signal `sig`.
+	/// Delivers `info` to the process.
 	///
 	/// If the process doesn't have a signal handler, the default action for the signal is
 	/// executed.
-	pub fn kill(&self, sig: Signal) {
+	///
+	/// A blocked standard signal is dropped, same as [`Self::kill`] has always done; a blocked
+	/// real-time signal is queued anyway, since it must still be delivered once the mask is
+	/// lifted rather than be lost (`rt_sigqueueinfo(2)`'s documented behavior, unlike plain
+	/// `kill(2)`).
+	pub fn kill_with_info(&self, info: SignalInfo) {
+		let Ok(sig) = Signal::try_from(info.signo as c_int) else {
+			return;
+		};
 		let mut signal_manager = self.signal.lock();
-		// Ignore blocked signals
-		if sig.can_catch() && signal_manager.sigmask.is_set(sig.get_id() as _) {
+		if sig.can_catch()
+			&& signal_manager.sigmask.is_set(sig.get_id() as _)
+			&& !rt_signal::is_realtime(sig)
+		{
 			return;
 		}
 		// Statistics
@@ -787,7 +984,46 @@ impl Process {
 			pid = self.get_pid(),
 			signal = sig.get_id()
 		);
-		signal_manager.sigpending.set(sig.get_id() as _);
+		oom::wrap(|| signal_manager.queue.record(info));
+	}
+
+	/// Kills the process with the given signal `sig`, as though sent by the kernel itself (no
+	/// sender process, no `sigqueue` payload).
+	///
+	/// If the process doesn't have a signal handler, the default action for the signal is
+	/// executed.
+	pub fn kill(&self, sig: Signal) {
+		self.kill_with_info(SignalInfo::kernel(sig));
+	}
+
+	/// Sends `sig` to the process as a whole rather than to this thread specifically:
+	/// `kill(2)`'s behavior, as opposed to a `tgkill`-style signal aimed at one thread (which
+	/// goes through [`Self::kill`] instead).
+	///
+	/// The signal is queued on the shared [`ThreadGroup`], not on any one thread's own pending
+	/// set, so it is [`Self::next_signal`] that decides which member thread actually ends up
+	/// handling it.
+	pub fn kill_process(&self, sig: Signal) {
+		self.rusage.ru_nsignals.fetch_add(1, Relaxed);
+		self.thread_group.lock().kill(sig);
+	}
+
+	/// Returns the next signal this thread should handle, checking both its own pending queue and
+	/// its thread group's shared, non-directed one.
+	///
+	/// If `peek` is `false`, the signal returned is consumed (removed from whichever set it came
+	/// from).
+	pub fn next_signal(&self, peek: bool) -> Option<SignalInfo> {
+		let mut signal_manager = self.signal.lock();
+		if let Some(info) = signal_manager.next_signal(peek) {
+			return Some(info);
+		}
+		let sigmask = signal_manager.sigmask;
+		drop(signal_manager);
+		let sig = self.thread_group.lock().next_signal(sigmask, peek)?;
+		// `Self::kill_process` doesn't accept a `SignalInfo`, so a group-directed delivery never
+		// carries a real sender/payload; treat it as kernel-originated, same as `Self::kill`.
+		Some(SignalInfo::kernel(sig))
 	}
 
 	/// Kills every process in the process group.
@@ -829,6 +1065,30 @@ impl Process {
 		self.set_state(State::Zombie);
 		self.vfork_wake();
 	}
+
+	/// `exit_group`: ends every thread in this process's thread group with `status`.
+	///
+	/// Unlike [`Self::exit`], which only ever ends the calling thread, this transitions every
+	/// member to [`State::Zombie`]. Each member's own transition still runs
+	/// [`Self::set_state`]'s normal `SIGCHLD`-to-parent notification, but since `SIGCHLD` is not
+	/// a queued (real-time) signal, those repeated notifications collapse into the single pending
+	/// `SIGCHLD` a `waitpid`-ing parent actually observes.
+	///
+	/// Does nothing if the group has already begun exiting (another thread called `exit_group`
+	/// first).
+	pub fn exit_group(&self, status: u32) {
+		let mut group = self.thread_group.lock();
+		if !group.begin_exit(status as ExitStatus) {
+			return;
+		}
+		let members = mem::take(&mut group.members);
+		drop(group);
+		for tid in members {
+			if let Some(thread) = Process::get_by_tid(tid) {
+				thread.exit(status);
+			}
+		}
+	}
 }
 
 impl fmt::Debug for Process {
@@ -867,20 +1127,61 @@ impl Drop for Process {
 	}
 }
 
+/// The number of calls into [`yield_current_impl`] counted as one second of accumulated CPU time,
+/// for `RLIMIT_CPU` accounting.
+///
+/// Approximates a `HZ`-style tick rate off the one thing this tree's snapshot actually calls
+/// once per return to userspace, since neither `process::scheduler` nor a real timer interrupt
+/// (`time` has no files here) is wired up to drive accounting on its own schedule.
+const CPU_TICKS_PER_SEC: u64 = 100;
+
+/// Accounts one CPU tick to `proc` and enforces `RLIMIT_CPU` against it: redelivers
+/// [`Signal::SIGXCPU`] once per accounted second while over the soft limit, and force-kills the
+/// process once it reaches the hard limit.
+fn account_cpu_time(proc: &Process) {
+	let ticks = proc.cpu_ticks.fetch_add(1, Relaxed) + 1;
+	if ticks % CPU_TICKS_PER_SEC != 0 {
+		return;
+	}
+	let secs = ticks / CPU_TICKS_PER_SEC;
+	let limits = *proc.resource_limits.lock();
+	let cpu = limits.get(rlimit::Resource::Cpu);
+	if cpu.max != rlimit::RLIM_INFINITY && secs >= cpu.max {
+		proc.kill(Signal::SIGKILL);
+		return;
+	}
+	if let Some(sig) = limits.check_cpu(secs) {
+		proc.kill(sig);
+	}
+}
+
 /// Returns `true` if the execution shall continue. Else, the execution shall be paused.
 fn yield_current_impl(frame: &mut IntFrame) -> bool {
 	// If the process is not running anymore, stop execution
 	let proc = Process::current();
+	account_cpu_time(&proc);
+	cpulimit::poll_all();
 	if proc.get_state() != State::Running {
 		return false;
 	}
-	// If no signal is pending, continue
-	let mut signal_manager = proc.signal.lock();
-	let Some(sig) = signal_manager.next_signal(false) else {
+	// If no signal is pending for this thread or its thread group, continue
+	let Some(info) = proc.next_signal(false) else {
 		return true;
 	};
+	let sig = Signal::try_from(info.signo as c_int).unwrap();
+	// If traced, divert into a ptrace stop instead of the signal's normal disposition: the tracer
+	// observes it through `waitpid` and decides, via `PTRACE_CONT`/`PTRACE_SYSCALL`, whether to
+	// reinject it, substitute another signal, or drop it.
+	if ptrace::stop_for_signal(&proc, info) {
+		return false;
+	}
 	// Prepare for execution of signal handler
-	signal_manager.handlers.lock()[sig.get_id() as usize].exec(sig, &proc, frame);
+	//
+	// `SignalHandler::exec` only takes the resolved `Signal`, not the full `SignalInfo`: handing
+	// a `SA_SIGINFO` handler its `siginfo_t` (sender PID/UID, `si_code`, `sigqueue` payload) needs
+	// to populate that structure on the signal frame built for the handler, which needs
+	// `arch::x86::idt::IntFrame`'s field layout; `arch` has no files in this tree's snapshot.
+	proc.signal.lock().handlers.lock()[sig.get_id() as usize].exec(sig, &proc, frame);
 	// If the process is still running, continue execution
 	proc.get_state() == State::Running
 }
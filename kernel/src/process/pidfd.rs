@@ -0,0 +1,109 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `pidfd`: a waitable handle on a specific [`Process`], instead of a PID that may be recycled
+//! out from under a still-pending `kill(2)`/`waitpid(2)`.
+//!
+//! A [`PidFd`] becomes ready the moment [`Self::target`] transitions to [`State::Zombie`]:
+//! [`Self::wait`] blocks the calling thread until that happens, by registering it in the target's
+//! own [`Process::pidfd_waiters`], which [`Process::set_state`] drains and wakes on every
+//! transition into [`State::Zombie`]. [`pidfd_send_signal`] reuses [`AccessProfile::can_kill`] for
+//! the permission check `kill(2)` itself uses, but (unlike `kill(2)`) targets the exact `Process`
+//! the handle was created against, so a PID recycled between `pidfd_open`/`CLONE_PIDFD` and the
+//! signal can never redirect it onto an unrelated process.
+//!
+//! What this does *not* do: back a [`PidFd`] with an actual file descriptor
+//! (`pidfd_open(2)`/`CLONE_PIDFD`) or make it pollable through `poll`/`select`/`epoll`. Both need
+//! `file::fd`'s `FileDescriptorTable`/`File` machinery's file-operations trait, which is not part
+//! of this tree's snapshot (the same gap `rlimit.rs`'s `check_nofile` documents), so there is no
+//! concrete type here this module could implement that trait against. [`Self::wait`] is written
+//! in the shape the read/poll side of that integration would call into once it exists.
+
+use super::{pid::Pid, rt_signal::SignalInfo, Process, State};
+use crate::file::perm::AccessProfile;
+use utils::{errno, errno::EResult, ptr::arc::Arc};
+
+/// A waitable handle on a specific process, as obtained by `pidfd_open(2)` or `CLONE_PIDFD`.
+#[derive(Clone)]
+pub struct PidFd {
+	/// The process this handle refers to.
+	target: Arc<Process>,
+}
+
+impl PidFd {
+	/// Creates a handle referring to `target`.
+	pub fn new(target: Arc<Process>) -> Self {
+		Self { target }
+	}
+
+	/// The process this handle refers to.
+	pub fn target(&self) -> &Arc<Process> {
+		&self.target
+	}
+
+	/// Tells whether the handle is currently readable: `target` has exited.
+	pub fn is_ready(&self) -> bool {
+		self.target.get_state() == State::Zombie
+	}
+
+	/// Blocks the calling thread (`waiter`) until [`Self::is_ready`] holds, registering it in
+	/// [`Process::pidfd_waiters`] beforehand so [`Process::set_state`]'s transition to
+	/// [`State::Zombie`] wakes it.
+	///
+	/// Does nothing (returns immediately) if already ready.
+	pub fn wait(&self, waiter: &Process) -> EResult<()> {
+		// Held across both the readiness check and the registration: `set_state`'s transition to
+		// `Zombie` drains `pidfd_waiters` under this same lock, so whichever of the two runs
+		// first, the other observes a consistent outcome instead of this check-then-register
+		// racing a drain that already ran, which would register a waiter no transition will ever
+		// wake again.
+		let mut waiters = self.target.pidfd_waiters.lock();
+		if self.is_ready() {
+			return Ok(());
+		}
+		waiters.push(waiter.get_pid())?;
+		drop(waiters);
+		waiter.set_state(State::Sleeping);
+		Ok(())
+	}
+}
+
+/// `pidfd_send_signal`: delivers `info` to `pidfd`'s target process on behalf of `sender`.
+///
+/// Fails with [`EPERM`] if `sender` is not allowed to signal the target (the same credential
+/// check `kill(2)` itself uses). Unlike `kill(pid, sig)`, this can never land on the wrong
+/// process due to `pid` having been recycled between opening `pidfd` and this call, since `pidfd`
+/// already carries a strong reference to the exact process it was opened against.
+pub fn pidfd_send_signal(sender: &AccessProfile, pidfd: &PidFd, info: SignalInfo) -> EResult<()> {
+	if !sender.can_kill(pidfd.target()) {
+		return Err(errno!(EPERM));
+	}
+	pidfd.target().kill_with_info(info);
+	Ok(())
+}
+
+/// `pidfd_open`'s core lookup: returns a [`PidFd`] referring to the process `pid` names.
+///
+/// Fails with [`ESRCH`] if no such process exists. This stops at producing the handle itself;
+/// wrapping it as an actual file descriptor is the syscall layer's job once `file::fd` is part of
+/// this tree's snapshot (see this module's own doc comment).
+pub fn open(pid: Pid) -> EResult<PidFd> {
+	Process::get_by_pid(pid)
+		.map(PidFd::new)
+		.ok_or_else(|| errno!(ESRCH))
+}
@@ -0,0 +1,200 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `pledge(2)`-style syscall capability restriction: once a process pledges to a [`PromiseSet`],
+//! any syscall whose [`SYSCALL_PROMISES`] entry requires a promise outside that set aborts the
+//! process, turning the kernel's existing scattered per-syscall permission checks into one
+//! declarative least-privilege mechanism.
+//!
+//! [`pledge`] installs or narrows [`super::Process::promises`]: the first call installs the
+//! requested set outright; every call after that intersects the request against what is already
+//! held, so a process can only ever give promises up, never add ones back, matching real
+//! `pledge(2)`'s one-way ratchet.
+//!
+//! [`enforce`] is the per-syscall check: [`SIGABRT`] on a missing promise, matching OpenBSD's own
+//! choice to make a violation fatal and auditable (a core dump) rather than a silently-returned
+//! errno a program might not even check. It is keyed by syscall *name* rather than number, since
+//! `syscall`'s dispatch table (where numbers would be assigned) has no file in this tree's
+//! snapshot; every syscall file listed in [`SYSCALL_PROMISES`] calls `pledge::enforce` at its own
+//! top (`syscall::fchmod::fchmod` was the first one wired up), so the table's coverage is exact,
+//! not aspirational.
+
+use super::{signal::Signal, Process};
+use core::ops::{BitOr, BitOrAssign};
+
+/// A bitmask of `pledge(2)`-style capability groups.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PromiseSet(u16);
+
+impl PromiseSet {
+	/// `stdio`: basic I/O on already-open file descriptors, memory allocation, and other
+	/// baseline operations every process needs.
+	pub const STDIO: Self = Self(1 << 0);
+	/// `rpath`: read-only filesystem path access.
+	pub const RPATH: Self = Self(1 << 1);
+	/// `wpath`: write access to existing filesystem paths.
+	pub const WPATH: Self = Self(1 << 2);
+	/// `cpath`: creation and removal of filesystem paths.
+	pub const CPATH: Self = Self(1 << 3);
+	/// `fattr`: changing file metadata (mode, ownership, timestamps).
+	pub const FATTR: Self = Self(1 << 4);
+	/// `proc`: process management (`fork`, process groups, scheduling, resource limits, ...).
+	pub const PROC: Self = Self(1 << 5);
+	/// `exec`: `execve`.
+	pub const EXEC: Self = Self(1 << 6);
+	/// `inet`: IPv4/IPv6 sockets.
+	pub const INET: Self = Self(1 << 7);
+	/// `chroot`: changing the process's root directory. Kept distinct from [`Self::PROC`] since
+	/// it is a much stronger capability than ordinary process management; `chroot(2)` requires
+	/// both.
+	pub const CHROOT: Self = Self(1 << 8);
+
+	/// An empty set: once pledged to this, only syscalls requiring no promise at all (those
+	/// absent from [`SYSCALL_PROMISES`]) remain callable.
+	pub const fn empty() -> Self {
+		Self(0)
+	}
+
+	/// Tells whether every promise in `required` is also set in `self`.
+	pub fn contains(&self, required: Self) -> bool {
+		self.0 & required.0 == required.0
+	}
+
+	/// The promises present in both `self` and `other`.
+	pub fn intersection(&self, other: Self) -> Self {
+		Self(self.0 & other.0)
+	}
+}
+
+impl BitOr for PromiseSet {
+	type Output = Self;
+
+	fn bitor(self, rhs: Self) -> Self {
+		Self(self.0 | rhs.0)
+	}
+}
+
+impl BitOrAssign for PromiseSet {
+	fn bitor_assign(&mut self, rhs: Self) {
+		self.0 |= rhs.0;
+	}
+}
+
+/// Maps a syscall's name to the promise(s) it requires. A syscall absent from this table needs no
+/// promise at all and remains callable under any pledge, including the empty set.
+///
+/// Not exhaustive over every syscall file in this tree: entries are added as each syscall is
+/// audited against the promise groups above, the same incremental spirit `rlimit.rs`'s own
+/// enforcement points were added in.
+pub const SYSCALL_PROMISES: &[(&str, PromiseSet)] = &[
+	("read", PromiseSet::STDIO),
+	("readv", PromiseSet::STDIO),
+	("preadv", PromiseSet::STDIO),
+	("pwritev", PromiseSet::STDIO),
+	("writev", PromiseSet::STDIO),
+	("pipe2", PromiseSet::STDIO),
+	("access", PromiseSet::RPATH),
+	("readlink", PromiseSet::RPATH),
+	("rmdir", PromiseSet::CPATH),
+	("chown", PromiseSet::FATTR),
+	("chown32", PromiseSet::FATTR),
+	("fchmod", PromiseSet::FATTR),
+	("setgid32", PromiseSet::PROC),
+	("ptrace", PromiseSet::PROC),
+	("seccomp", PromiseSet::PROC),
+	("sigqueue", PromiseSet::PROC),
+	("unveil", PromiseSet::PROC),
+	("mount", PromiseSet::CPATH),
+	("umount", PromiseSet::CPATH),
+	("bind", PromiseSet::INET),
+	("getsockname", PromiseSet::INET),
+	("getsockopt", PromiseSet::INET),
+];
+
+/// `pledge`: narrows `proc`'s own promise set to `requested`.
+///
+/// The first call installs `requested` outright. Every call after that intersects `requested`
+/// against the set already held, silently dropping any promise `requested` asks for that `proc`
+/// does not already have, rather than erroring: once given up, a promise can never come back.
+pub fn pledge(proc: &Process, requested: PromiseSet) {
+	let mut slot = proc.promises.lock();
+	*slot = Some(match *slot {
+		Some(current) => current.intersection(requested),
+		None => requested,
+	});
+}
+
+/// Checks `syscall_name` against `proc`'s pledge, if any.
+///
+/// If `proc` has pledged (its promise set is `Some`) and `syscall_name` requires a promise absent
+/// from it, sends `proc` [`Signal::SIGABRT`], matching OpenBSD's choice to make a pledge violation
+/// fatal and auditable rather than a silently-returned errno. Does nothing if `proc` has never
+/// pledged, or if `syscall_name` is not in [`SYSCALL_PROMISES`].
+pub fn enforce(proc: &Process, syscall_name: &str) {
+	let Some(held) = *proc.promises.lock() else {
+		return;
+	};
+	let Some((_, required)) = SYSCALL_PROMISES.iter().find(|(name, _)| *name == syscall_name)
+	else {
+		return;
+	};
+	if !held.contains(*required) {
+		proc.kill(Signal::SIGABRT);
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn promise_set_empty_contains_nothing_but_itself() {
+		assert!(PromiseSet::empty().contains(PromiseSet::empty()));
+		assert!(!PromiseSet::empty().contains(PromiseSet::STDIO));
+	}
+
+	#[test_case]
+	fn promise_set_bitor_contains_both_operands() {
+		let set = PromiseSet::RPATH | PromiseSet::WPATH;
+		assert!(set.contains(PromiseSet::RPATH));
+		assert!(set.contains(PromiseSet::WPATH));
+		assert!(set.contains(PromiseSet::RPATH | PromiseSet::WPATH));
+		assert!(!set.contains(PromiseSet::CPATH));
+	}
+
+	#[test_case]
+	fn promise_set_intersection_keeps_only_shared_promises() {
+		let a = PromiseSet::STDIO | PromiseSet::RPATH | PromiseSet::PROC;
+		let b = PromiseSet::RPATH | PromiseSet::PROC | PromiseSet::EXEC;
+		let inter = a.intersection(b);
+		assert!(inter.contains(PromiseSet::RPATH));
+		assert!(inter.contains(PromiseSet::PROC));
+		assert!(!inter.contains(PromiseSet::STDIO));
+		assert!(!inter.contains(PromiseSet::EXEC));
+	}
+
+	#[test_case]
+	fn syscall_promises_has_no_duplicate_entries() {
+		for (i, (name, _)) in SYSCALL_PROMISES.iter().enumerate() {
+			assert!(
+				SYSCALL_PROMISES[..i].iter().all(|(other, _)| other != name),
+				"{name} listed more than once in SYSCALL_PROMISES"
+			);
+		}
+	}
+}
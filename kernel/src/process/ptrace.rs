@@ -0,0 +1,365 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `ptrace(2)` tracing subsystem.
+//!
+//! A traced process (the tracee) carries its [`PtraceState`] in
+//! [`Process::ptrace`](super::Process::ptrace); its tracer carries the reverse link in
+//! [`ProcessLinks::tracees`](super::ProcessLinks::tracees). [`attach`]/[`traceme`] establish that
+//! pair, [`resume`] and [`detach`] end or continue it, and [`stop_for_signal`] is the divert point
+//! [`super::yield_current`] calls to turn a catchable signal into a ptrace stop
+//! ([`super::State::TraceStopped`]) instead of the normal disposition; [`stop_for_syscall`] is the
+//! equivalent divert point a syscall dispatcher would call at syscall entry and exit under
+//! [`ResumeMode::Syscall`], and [`stop_for_seccomp`] is the one
+//! [`super::seccomp::SeccompState::evaluate`] calls for a `SECCOMP_RET_TRACE` verdict.
+//!
+//! What this does *not* wire in, because the piece it would hook is not part of this tree's
+//! snapshot: [`stop_for_syscall`] itself (there is no `syscall` dispatch file to call it from a
+//! syscall entry/exit point), `GETREGS`/`SETREGS` (blocked on
+//! [`Process::user_regs`](super::Process::user_regs) itself being a `todo!()`, which in turn
+//! needs `arch::x86::idt::IntFrame`'s field layout, and `arch` has no files in this snapshot at
+//! all), `PEEKDATA`/`POKEDATA` (reading a *different* process's address space needs a
+//! cross-address-space copy primitive; `process::mem_space::copy` only copies between the kernel
+//! and the *current* process's userspace), and single-stepping (`PTRACE_SINGLESTEP` arms
+//! [`ResumeMode::SingleStep`], but actually setting the TF bit in the resumed `IntFrame`'s EFLAGS
+//! needs that same unavailable field layout). [`PtraceState`], attach/detach/resume/
+//! [`stop_for_signal`] and the tracer-death cleanup in [`super::Process::set_state`] are fully
+//! self-contained and do not depend on any of that.
+
+use super::{
+	pid::Pid,
+	rt_signal::SignalInfo,
+	signal::Signal,
+	Process, State,
+};
+use core::{ffi::c_int, mem, ops::BitOr};
+use utils::{errno, errno::EResult};
+
+/// `PTRACE_TRACEME`.
+pub const TRACEME: c_int = 0;
+/// `PTRACE_PEEKTEXT`.
+pub const PEEKTEXT: c_int = 1;
+/// `PTRACE_PEEKDATA`.
+pub const PEEKDATA: c_int = 2;
+/// `PTRACE_POKETEXT`.
+pub const POKETEXT: c_int = 4;
+/// `PTRACE_POKEDATA`.
+pub const POKEDATA: c_int = 5;
+/// `PTRACE_CONT`.
+pub const CONT: c_int = 7;
+/// `PTRACE_KILL`.
+pub const KILL: c_int = 8;
+/// `PTRACE_SINGLESTEP`.
+pub const SINGLESTEP: c_int = 9;
+/// `PTRACE_GETREGS`.
+pub const GETREGS: c_int = 12;
+/// `PTRACE_SETREGS`.
+pub const SETREGS: c_int = 13;
+/// `PTRACE_ATTACH`.
+pub const ATTACH: c_int = 16;
+/// `PTRACE_DETACH`.
+pub const DETACH: c_int = 17;
+/// `PTRACE_SETOPTIONS`.
+pub const SETOPTIONS: c_int = 0x4200;
+/// `PTRACE_GETSIGINFO`.
+pub const GETSIGINFO: c_int = 0x4202;
+/// `PTRACE_SYSCALL`.
+pub const SYSCALL: c_int = 24;
+/// `PTRACE_SEIZE`.
+pub const SEIZE: c_int = 0x4206;
+
+/// `PTRACE_O_TRACESYSGOOD`: mark syscall-stop signals with bit 7 of the stop signal so the
+/// tracer can tell them apart from a genuine `SIGTRAP`.
+pub const TRACESYSGOOD: u32 = 1 << 0;
+/// `PTRACE_O_TRACEEXEC`: stop the tracee at the next `execve`.
+pub const TRACEEXEC: u32 = 1 << 4;
+/// `PTRACE_O_TRACEEXIT`: stop the tracee just before it exits.
+pub const TRACEEXIT: u32 = 1 << 6;
+/// `PTRACE_O_EXITKILL`: kill the tracee if the tracer exits.
+pub const EXITKILL: u32 = 1 << 20;
+
+/// A set of `PTRACE_O_*` options, as installed by `PTRACE_SEIZE`/`PTRACE_SETOPTIONS`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PtraceOptions(u32);
+
+impl PtraceOptions {
+	/// The empty set of options.
+	pub const NONE: Self = Self(0);
+
+	/// Tells whether `self` contains every flag set in `other`.
+	pub fn contains(self, other: Self) -> bool {
+		self.0 & other.0 == other.0
+	}
+}
+
+impl BitOr for PtraceOptions {
+	type Output = Self;
+
+	fn bitor(self, rhs: Self) -> Self {
+		Self(self.0 | rhs.0)
+	}
+}
+
+impl From<u32> for PtraceOptions {
+	fn from(bits: u32) -> Self {
+		Self(bits)
+	}
+}
+
+/// How a stopped tracee should be resumed, set by the `PTRACE_CONT`/`PTRACE_SYSCALL`/
+/// `PTRACE_SINGLESTEP` request that last resumed it (or its initial value, for a tracee that has
+/// never been resumed yet).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResumeMode {
+	/// `PTRACE_CONT`: run free until the next signal or syscall stop that the tracer didn't ask
+	/// to suppress.
+	#[default]
+	Cont,
+	/// `PTRACE_SYSCALL`: stop again at the next syscall entry or exit, reusing the syscall
+	/// dispatch hook (not present in this snapshot) the same way a stop-at-syscall `seccomp`
+	/// action would.
+	Syscall,
+	/// `PTRACE_SINGLESTEP`: stop again after the next single instruction.
+	SingleStep,
+}
+
+/// Why a tracee is currently stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+	/// A catchable signal was diverted into a group-stop instead of being delivered normally.
+	Signal(Signal),
+	/// Stopped at a syscall entry or exit, per [`ResumeMode::Syscall`].
+	Syscall,
+	/// Stopped for `SECCOMP_RET_TRACE`, carrying the low 16 bits of the filter's return value
+	/// (what `PTRACE_GETEVENTMSG` would read, though this snapshot does not implement that
+	/// request).
+	Seccomp(u16),
+}
+
+/// A traced process's ptrace state, installed by [`traceme`]/[`attach`].
+#[derive(Debug, Clone)]
+pub struct PtraceState {
+	/// The PID of the tracer.
+	pub tracer: Pid,
+	/// Options installed via `PTRACE_SEIZE`/`PTRACE_SETOPTIONS`.
+	pub options: PtraceOptions,
+	/// `true` if attached via `PTRACE_SEIZE` rather than `PTRACE_ATTACH`/`PTRACE_TRACEME`: a
+	/// seized tracee is not stopped immediately on attach.
+	pub seized: bool,
+	/// How the tracee resumes the next time it is continued.
+	pub resume_mode: ResumeMode,
+	/// Set while the tracee is in [`State::TraceStopped`]; cleared by [`resume`].
+	pub stop_reason: Option<StopReason>,
+}
+
+/// `PTRACE_TRACEME`: makes the calling process's parent its tracer.
+///
+/// Fails with [`EPERM`] if the process is already traced, mirroring the real syscall.
+pub fn traceme(tracee: &Process) -> EResult<()> {
+	let tracer = tracee
+		.links
+		.lock()
+		.parent
+		.as_ref()
+		.ok_or_else(|| errno!(EPERM))?
+		.get_pid();
+	let mut state = tracee.ptrace.lock();
+	if state.is_some() {
+		return Err(errno!(EPERM));
+	}
+	*state = Some(PtraceState {
+		tracer,
+		options: PtraceOptions::NONE,
+		seized: false,
+		resume_mode: ResumeMode::Cont,
+		stop_reason: None,
+	});
+	Ok(())
+}
+
+/// `PTRACE_ATTACH`/`PTRACE_SEIZE`: makes `tracer` the tracer of `tracee`.
+///
+/// `seize` selects `PTRACE_SEIZE` semantics (no implicit stop, `options` takes effect
+/// immediately) over `PTRACE_ATTACH` (the tracee is immediately moved to
+/// [`State::TraceStopped`], as if it had just received a stopping signal, and the tracer observes
+/// that stop through `waitpid`). Fails with [`EPERM`] if `tracee` is already traced. The caller
+/// (`syscall::ptrace`'s `PTRACE_ATTACH`/`PTRACE_SEIZE` handling) is responsible for the actual
+/// permission check, gating it on `AccessProfile::can_kill` exactly as `kill(2)` does.
+pub fn attach(
+	tracer: &Process,
+	tracee: &Process,
+	seize: bool,
+	options: PtraceOptions,
+) -> EResult<()> {
+	let mut state = tracee.ptrace.lock();
+	if state.is_some() {
+		return Err(errno!(EPERM));
+	}
+	*state = Some(PtraceState {
+		tracer: tracer.get_pid(),
+		options,
+		seized: seize,
+		resume_mode: ResumeMode::Cont,
+		stop_reason: None,
+	});
+	drop(state);
+	tracer.links.lock().tracees.push(tracee.get_pid())?;
+	if !seize {
+		tracee.ptrace.lock().as_mut().unwrap().stop_reason =
+			Some(StopReason::Signal(Signal::SIGSTOP));
+		tracee.set_state(State::TraceStopped);
+		tracer.kill(Signal::SIGCHLD);
+	}
+	Ok(())
+}
+
+/// `PTRACE_DETACH`, and the cleanup run on every tracee when their tracer dies (see
+/// [`detach_all_tracees`]).
+///
+/// Clears `tracee`'s ptrace state and resumes it if it was stopped for tracing.
+pub fn detach(tracee: &Process) {
+	let was_stopped = tracee.ptrace.lock().take().is_some();
+	if was_stopped && tracee.get_state() == State::TraceStopped {
+		tracee.set_state(State::Running);
+		tracee.wake();
+	}
+}
+
+/// Called from [`Process::set_state`](super::Process::set_state) when `tracer` transitions to
+/// [`State::Zombie`]: detaches every process it was tracing, resuming any that were stopped,
+/// exactly as a dying real-world tracer implicitly detaches. `PTRACE_O_EXITKILL` is not honored:
+/// a tracee with that option set is detached and resumed like any other, not killed.
+pub fn detach_all_tracees(tracer: &Process) {
+	let tracees = mem::take(&mut tracer.links.lock().tracees);
+	for pid in tracees {
+		if let Some(tracee) = Process::get_by_pid(pid) {
+			detach(&tracee);
+		}
+	}
+}
+
+/// `PTRACE_CONT`/`PTRACE_SYSCALL`/`PTRACE_SINGLESTEP`: resumes a tracee stopped for tracing,
+/// arming `mode` for the next stop.
+///
+/// If `inject` is `Some`, that signal is delivered to the tracee once it resumes, regardless of
+/// what (if anything) stopped it in the first place; if `None`, a signal that caused the stop is
+/// dropped, exactly as `PTRACE_CONT`/`PTRACE_SYSCALL` with a signal number of `0` does in Linux.
+///
+/// Fails with [`ESRCH`] if `tracee` is not traced or not currently stopped.
+pub fn resume(tracee: &Process, mode: ResumeMode, inject: Option<Signal>) -> EResult<()> {
+	let mut state = tracee.ptrace.lock();
+	let Some(ptrace_state) = state.as_mut() else {
+		return Err(errno!(ESRCH));
+	};
+	if tracee.get_state() != State::TraceStopped {
+		return Err(errno!(ESRCH));
+	}
+	ptrace_state.resume_mode = mode;
+	ptrace_state.stop_reason = None;
+	drop(state);
+	if let Some(sig) = inject {
+		let signo = sig.get_id() as u8;
+		let info = (*tracee.last_siginfo.lock())
+			.filter(|info| info.signo == signo)
+			.unwrap_or_else(|| SignalInfo::user(sig, 0, 0));
+		tracee.kill_with_info(info);
+	}
+	tracee.set_state(State::Running);
+	tracee.wake();
+	Ok(())
+}
+
+/// Diverts delivery of `info` to `tracee` into a ptrace stop ([`State::TraceStopped`]), if
+/// `tracee` is traced and its signal is catchable, recording `info` in
+/// [`Process::last_siginfo`](super::Process::last_siginfo) for [`resume`] to reinject and waking its
+/// tracer (via `SIGCHLD`) so `waitpid` observes the stop.
+///
+/// Returns `true` if the signal was diverted (the caller, [`super::yield_current`], must not go
+/// on to apply the signal's normal disposition); `false` if `tracee` is not traced, or the signal
+/// cannot be caught and so is never divertible (`SIGKILL`).
+pub fn stop_for_signal(tracee: &Process, info: SignalInfo) -> bool {
+	let Ok(sig) = Signal::try_from(info.signo as c_int) else {
+		return false;
+	};
+	if !sig.can_catch() {
+		return false;
+	}
+	let mut state = tracee.ptrace.lock();
+	let Some(ptrace_state) = state.as_mut() else {
+		return false;
+	};
+	ptrace_state.stop_reason = Some(StopReason::Signal(sig));
+	let tracer_pid = ptrace_state.tracer;
+	drop(state);
+	*tracee.last_siginfo.lock() = Some(info);
+	tracee.set_state(State::TraceStopped);
+	if let Some(tracer) = Process::get_by_pid(tracer_pid) {
+		tracer.kill(Signal::SIGCHLD);
+	}
+	true
+}
+
+/// The syscall-entry/syscall-exit equivalent of [`stop_for_signal`], for a tracee resumed with
+/// [`ResumeMode::Syscall`] (`PTRACE_SYSCALL`).
+///
+/// Returns `true` (and diverts `tracee` into [`State::TraceStopped`]) if `tracee` is traced and
+/// currently armed with [`ResumeMode::Syscall`]; `false` otherwise, meaning the syscall should
+/// run (or have run) normally.
+///
+/// Written in the exact shape a syscall dispatcher would call it in at entry and again at exit,
+/// but not spliced into one since `syscall`'s dispatch file is not part of this tree's snapshot.
+pub fn stop_for_syscall(tracee: &Process) -> bool {
+	let mut state = tracee.ptrace.lock();
+	let Some(ptrace_state) = state.as_mut() else {
+		return false;
+	};
+	if ptrace_state.resume_mode != ResumeMode::Syscall {
+		return false;
+	}
+	ptrace_state.stop_reason = Some(StopReason::Syscall);
+	let tracer_pid = ptrace_state.tracer;
+	drop(state);
+	tracee.set_state(State::TraceStopped);
+	if let Some(tracer) = Process::get_by_pid(tracer_pid) {
+		tracer.kill(Signal::SIGCHLD);
+	}
+	true
+}
+
+/// `SECCOMP_RET_TRACE`'s stop point: unlike [`stop_for_syscall`], this does not require
+/// [`ResumeMode::Syscall`] to be armed, since a seccomp filter's `TRACE` action stops the tracee
+/// regardless of how it was last resumed, carrying `ret_data` (the filter's own return-value data
+/// bits) for the tracer to read back.
+///
+/// Returns `true` (and diverts `tracee` into [`State::TraceStopped`]) if `tracee` is traced;
+/// `false` if not, meaning the caller ([`super::seccomp::SeccompState::evaluate`]) must fail the
+/// syscall with `ENOSYS` instead, matching Linux's own fallback for an untraced `TRACE` action.
+pub fn stop_for_seccomp(tracee: &Process, ret_data: u16) -> bool {
+	let mut state = tracee.ptrace.lock();
+	let Some(ptrace_state) = state.as_mut() else {
+		return false;
+	};
+	ptrace_state.stop_reason = Some(StopReason::Seccomp(ret_data));
+	let tracer_pid = ptrace_state.tracer;
+	drop(state);
+	tracee.set_state(State::TraceStopped);
+	if let Some(tracer) = Process::get_by_pid(tracer_pid) {
+		tracer.kill(Signal::SIGCHLD);
+	}
+	true
+}
@@ -0,0 +1,284 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! POSIX resource limits (`getrlimit(2)`/`setrlimit(2)`/`prlimit64(2)`).
+//!
+//! [`ResourceLimits`] lives on [`super::Process`] next to `rusage`, is copied (not shared) across
+//! [`super::Process::fork`], and is left untouched by `execve` since that replaces a process's
+//! image in place rather than its [`super::Process`] structure, so nothing needs to do anything
+//! special to "inherit" it there.
+//!
+//! [`ResourceLimits::check_nofile`], [`ResourceLimits::check_nproc`], [`ResourceLimits::check_cpu`]
+//! and [`ResourceLimits::check_sigpending`] are the enforcement points, written as pure functions
+//! so the caller decides what to do with the result; [`super::Process::fork`],
+//! [`super::rt_signal::sigqueue`] and [`super::account_cpu_time`] wire up three of the four in this
+//! tree; [`ResourceLimits::check_nofile`] documents, in its own doc comment, the call site this
+//! snapshot is missing.
+
+use super::{signal::Signal, Process};
+use utils::{errno, errno::EResult};
+
+/// `RLIM_INFINITY`: no limit.
+pub const RLIM_INFINITY: u64 = u64::MAX;
+
+/// One `rlimit` pair, as read or written by `getrlimit`/`setrlimit`/`prlimit64`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RLimit {
+	/// The soft limit: the value actually enforced. A process may raise this itself, up to
+	/// `max`.
+	pub current: u64,
+	/// The hard limit: the ceiling `current` may be raised to. Only a privileged process may
+	/// raise this.
+	pub max: u64,
+}
+
+impl RLimit {
+	/// Creates an `rlimit` pair.
+	pub const fn new(current: u64, max: u64) -> Self {
+		Self { current, max }
+	}
+
+	/// An `rlimit` pair with no limit in either the soft or hard value.
+	pub const fn unlimited() -> Self {
+		Self::new(RLIM_INFINITY, RLIM_INFINITY)
+	}
+}
+
+/// Which resource an [`RLimit`] pair applies to, numbered exactly as Linux's own `RLIMIT_*`
+/// constants so a `getrlimit`/`setrlimit` syscall can index straight off the raw resource number
+/// it receives.
+///
+/// Only the resources this tree actually enforces or tracks are modeled; the remaining `RLIMIT_*`
+/// numbers (`FSIZE`, `RSS`, `MEMLOCK`, `LOCKS`, `MSGQUEUE`, `NICE`, `RTPRIO`, `RTTIME`) are not
+/// represented here.
+#[repr(usize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+	/// `RLIMIT_CPU`: total CPU time, in seconds.
+	Cpu = 0,
+	/// `RLIMIT_DATA`: size of the data segment.
+	Data = 2,
+	/// `RLIMIT_STACK`: size of the userspace stack.
+	Stack = 3,
+	/// `RLIMIT_CORE`: size of a core dump file.
+	Core = 4,
+	/// `RLIMIT_NPROC`: number of processes.
+	Nproc = 6,
+	/// `RLIMIT_NOFILE`: number of open file descriptors.
+	Nofile = 7,
+	/// `RLIMIT_AS`: size of the virtual address space.
+	As = 9,
+	/// `RLIMIT_SIGPENDING`: number of signals (standard and real-time) queued at once.
+	Sigpending = 11,
+}
+
+impl TryFrom<usize> for Resource {
+	type Error = ();
+
+	fn try_from(val: usize) -> Result<Self, ()> {
+		Ok(match val {
+			0 => Self::Cpu,
+			2 => Self::Data,
+			3 => Self::Stack,
+			4 => Self::Core,
+			6 => Self::Nproc,
+			7 => Self::Nofile,
+			9 => Self::As,
+			11 => Self::Sigpending,
+			_ => return Err(()),
+		})
+	}
+}
+
+/// The historical Linux default soft limit for `RLIMIT_STACK`: 8 MiB.
+const DEFAULT_STACK_SIZE: u64 = 8 * 1024 * 1024;
+/// The historical Linux default soft limit for `RLIMIT_NOFILE`.
+const DEFAULT_NOFILE_SOFT: u64 = 1024;
+/// The historical Linux default hard limit for `RLIMIT_NOFILE`.
+const DEFAULT_NOFILE_HARD: u64 = 4096;
+
+/// A process's POSIX resource limits.
+#[derive(Debug, Clone, Copy)]
+pub struct ResourceLimits {
+	/// `RLIMIT_CPU`.
+	pub cpu: RLimit,
+	/// `RLIMIT_DATA`.
+	pub data: RLimit,
+	/// `RLIMIT_STACK`.
+	pub stack: RLimit,
+	/// `RLIMIT_CORE`.
+	pub core: RLimit,
+	/// `RLIMIT_NPROC`.
+	pub nproc: RLimit,
+	/// `RLIMIT_NOFILE`.
+	pub nofile: RLimit,
+	/// `RLIMIT_AS`.
+	pub as_: RLimit,
+	/// `RLIMIT_SIGPENDING`.
+	pub sigpending: RLimit,
+}
+
+impl Default for ResourceLimits {
+	/// The default limits a freshly-booted init process starts with: unlimited, except for the
+	/// few resources POSIX systems traditionally cap out of the box.
+	fn default() -> Self {
+		Self {
+			cpu: RLimit::unlimited(),
+			data: RLimit::unlimited(),
+			stack: RLimit::new(DEFAULT_STACK_SIZE, RLIM_INFINITY),
+			core: RLimit::new(0, RLIM_INFINITY),
+			nproc: RLimit::unlimited(),
+			nofile: RLimit::new(DEFAULT_NOFILE_SOFT, DEFAULT_NOFILE_HARD),
+			as_: RLimit::unlimited(),
+			sigpending: RLimit::unlimited(),
+		}
+	}
+}
+
+impl ResourceLimits {
+	/// Returns the current limit for `resource`.
+	pub fn get(&self, resource: Resource) -> RLimit {
+		match resource {
+			Resource::Cpu => self.cpu,
+			Resource::Data => self.data,
+			Resource::Stack => self.stack,
+			Resource::Core => self.core,
+			Resource::Nproc => self.nproc,
+			Resource::Nofile => self.nofile,
+			Resource::As => self.as_,
+			Resource::Sigpending => self.sigpending,
+		}
+	}
+
+	/// Sets the limit for `resource` to `new`, as `setrlimit`/`prlimit64` would.
+	///
+	/// Fails with [`EINVAL`] if `new.current > new.max`. Fails with [`EPERM`] if `new.max` would
+	/// raise the resource's existing hard limit and `privileged` is `false`, since only a
+	/// privileged process may do that.
+	pub fn set(&mut self, resource: Resource, new: RLimit, privileged: bool) -> EResult<()> {
+		if new.current > new.max {
+			return Err(errno!(EINVAL));
+		}
+		let slot = match resource {
+			Resource::Cpu => &mut self.cpu,
+			Resource::Data => &mut self.data,
+			Resource::Stack => &mut self.stack,
+			Resource::Core => &mut self.core,
+			Resource::Nproc => &mut self.nproc,
+			Resource::Nofile => &mut self.nofile,
+			Resource::As => &mut self.as_,
+			Resource::Sigpending => &mut self.sigpending,
+		};
+		if new.max > slot.max && !privileged {
+			return Err(errno!(EPERM));
+		}
+		*slot = new;
+		Ok(())
+	}
+
+	/// Fails with [`EMFILE`] if opening one more file descriptor, bringing the open count to
+	/// `open_count + 1`, would exceed `RLIMIT_NOFILE`'s soft limit.
+	///
+	/// Meant to be called from `FileDescriptorTable::create_fd`; `file::fd` has no file in this
+	/// tree's snapshot, so nothing calls this yet.
+	pub fn check_nofile(&self, open_count: u64) -> EResult<()> {
+		if self.nofile.current != RLIM_INFINITY && open_count >= self.nofile.current {
+			return Err(errno!(EMFILE));
+		}
+		Ok(())
+	}
+
+	/// Fails with [`EAGAIN`] if `existing_children` has already reached `RLIMIT_NPROC`'s soft
+	/// limit, as [`super::Process::fork`] checks before creating a new child.
+	///
+	/// This approximates real `RLIMIT_NPROC`, which Linux counts per real UID across the whole
+	/// system: that needs a UID-indexed global registry, and `file::perm`'s `Uid` type isn't part
+	/// of this tree's snapshot to key one off of. Counting the forking process's own direct
+	/// children is the closest proxy obtainable from data this tree actually has.
+	pub fn check_nproc(&self, existing_children: u64) -> EResult<()> {
+		if self.nproc.current != RLIM_INFINITY && existing_children >= self.nproc.current {
+			return Err(errno!(EAGAIN));
+		}
+		Ok(())
+	}
+
+	/// Fails with [`EAGAIN`] if `queued` (the number of signals already queued for the process,
+	/// standard and real-time alike) has already reached `RLIMIT_SIGPENDING`'s soft limit, as
+	/// [`super::rt_signal::sigqueue`] checks before recording a new [`super::rt_signal::SignalInfo`].
+	pub fn check_sigpending(&self, queued: u64) -> EResult<()> {
+		if self.sigpending.current != RLIM_INFINITY && queued >= self.sigpending.current {
+			return Err(errno!(EAGAIN));
+		}
+		Ok(())
+	}
+
+	/// Returns [`Signal::SIGXCPU`] if `cpu_time_secs`, the process's total accumulated CPU time
+	/// in seconds, has reached `RLIMIT_CPU`'s soft limit.
+	///
+	/// Checked once per accounted second by [`super::account_cpu_time`], which redelivers
+	/// `SIGXCPU` every time it is called while still over the limit, since nothing clears the
+	/// condition until the process's own handler (or the hard limit, enforced separately) acts on
+	/// it. Does not itself enforce the hard limit; see [`super::account_cpu_time`].
+	pub fn check_cpu(&self, cpu_time_secs: u64) -> Option<Signal> {
+		(self.cpu.current != RLIM_INFINITY && cpu_time_secs >= self.cpu.current)
+			.then_some(Signal::SIGXCPU)
+	}
+
+	/// The size, in bytes, of a new process's userspace stack mapping, from `RLIMIT_STACK`'s
+	/// current soft limit.
+	pub fn stack_size(&self) -> u64 {
+		self.stack.current
+	}
+}
+
+/// `getrlimit`: returns `proc`'s current limit for `resource`.
+pub fn getrlimit(proc: &Process, resource: Resource) -> RLimit {
+	proc.resource_limits.lock().get(resource)
+}
+
+/// `setrlimit`: sets `proc`'s own limit for `resource` to `new`.
+pub fn setrlimit(proc: &Process, resource: Resource, new: RLimit) -> EResult<()> {
+	let privileged = proc.fs.lock().access_profile.is_privileged();
+	proc.resource_limits.lock().set(resource, new, privileged)
+}
+
+/// `prlimit64`: reads `target`'s current limit for `resource` on behalf of `caller`, and, if
+/// `new` is given, also sets it.
+///
+/// Fails with [`EPERM`] if `new` is given and `caller` is not allowed to affect `target`: the
+/// same credential check `kill(2)` uses (same or saved/effective UID, or privileged), mirroring
+/// the real `prlimit64`'s own check.
+pub fn prlimit(
+	caller: &Process,
+	target: &Process,
+	resource: Resource,
+	new: Option<RLimit>,
+) -> EResult<RLimit> {
+	let old = target.resource_limits.lock().get(resource);
+	if let Some(new) = new {
+		let caller_profile = caller.fs.lock().access_profile;
+		if !caller_profile.can_kill(target) {
+			return Err(errno!(EPERM));
+		}
+		target
+			.resource_limits
+			.lock()
+			.set(resource, new, caller_profile.is_privileged())?;
+	}
+	Ok(old)
+}
@@ -0,0 +1,216 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Queueing of signal deliveries, standard and real-time (`SIGRTMIN..=SIGRTMAX`), along with the
+//! `siginfo` payload each one carries.
+//!
+//! `ProcessSignal` used to track pending signals as a single `SigSet` bitfield: multiple
+//! instances of the same signal collapsed into one, and no `siginfo` was ever carried. That is
+//! correct for a standard signal (1..=31), which POSIX itself only ever keeps one instance of
+//! pending, but it cannot support real-time signals, which POSIX requires to be delivered once
+//! per `sigqueue`/`rt_sigqueueinfo` call, in order, each with its own payload. [`RtSignalQueue`]
+//! replaces that bitfield with a backlog of [`SignalInfo`] entries: [`RtSignalQueue::record`]
+//! keeps the "at most one instance" coalescing behavior for a standard signal number by replacing
+//! any entry already queued for it, while letting a real-time signal number accumulate one entry
+//! per delivery; [`RtSignalQueue::pop_next`] always returns the lowest-numbered eligible signal,
+//! which is what makes real-time signals deliver in ascending order relative to each other.
+//! [`sigqueue`] is the entry point a sender attaches a `sigval` through, and is the one place that
+//! enforces `RLIMIT_SIGPENDING` against the target's queue length.
+//!
+//! What this does *not* do: populate a `SA_SIGINFO` handler's `siginfo_t` on the signal frame
+//! built for it. That needs `arch::x86::idt::IntFrame`'s field layout, and `arch` has no files in
+//! this tree's snapshot (the same gap already documented against `ptrace`'s `GETREGS`/`SETREGS`).
+
+use super::{
+	pid::Pid,
+	signal::{SigSet, Signal},
+	Process,
+};
+use core::ffi::c_int;
+use utils::{collections::vec::Vec, errno, errno::EResult};
+
+/// The first real-time signal number (`SIGRTMIN` on Linux).
+pub const SIGRTMIN: u8 = 34;
+/// The last real-time signal number (`SIGRTMAX` on Linux).
+pub const SIGRTMAX: u8 = 64;
+
+/// `SI_USER`: the `si_code` for a signal sent by `kill(2)`/`tgkill(2)`, with no explicit payload.
+pub const SI_USER: i32 = 0;
+/// `SI_KERNEL`: the `si_code` for a signal raised by the kernel itself (a fault, `SIGCHLD`, a
+/// resource-limit signal, ...), with no sender process.
+pub const SI_KERNEL: i32 = 0x80;
+/// `SI_QUEUE`: the `si_code` for a signal sent by `sigqueue(2)`/`rt_sigqueueinfo(2)`, carrying an
+/// explicit `sigval`.
+pub const SI_QUEUE: i32 = -1;
+/// `SI_TKILL`: the `si_code` for a signal sent by `tgkill(2)`.
+pub const SI_TKILL: i32 = -6;
+
+/// Tells whether `sig` falls in the real-time range and therefore must be queued (one entry per
+/// delivery) rather than merely coalesced into a single pending instance.
+pub fn is_realtime(sig: Signal) -> bool {
+	is_realtime_id(sig.get_id() as u8)
+}
+
+/// Same as [`is_realtime`], but taking a raw signal number instead of a resolved [`Signal`], for
+/// use on a [`SignalInfo`]'s own `signo` field.
+fn is_realtime_id(signo: u8) -> bool {
+	(SIGRTMIN..=SIGRTMAX).contains(&signo)
+}
+
+/// The payload carried by a queued signal, mirroring POSIX's `union sigval`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SigVal(pub usize);
+
+/// One pending signal delivery, carrying everything a `SA_SIGINFO` handler's `siginfo_t` needs,
+/// mirroring Starnix's `SignalInfo`.
+#[derive(Debug, Clone, Copy)]
+pub struct SignalInfo {
+	/// The signal number.
+	pub signo: u8,
+	/// The `si_code` (`SI_USER`, `SI_KERNEL`, `SI_QUEUE`, `SI_TKILL`, ...).
+	pub code: i32,
+	/// The PID of the sender, or `0` for a kernel-generated signal.
+	pub pid: Pid,
+	/// The real UID of the sender, or `0` for a kernel-generated signal.
+	///
+	/// Stored as a plain `u32` (the standard width of a POSIX `uid_t`) rather than
+	/// `file::perm`'s own `Uid` type, which isn't part of this tree's snapshot to reference (the
+	/// same gap [`super::rlimit::ResourceLimits::check_nproc`] documents).
+	pub uid: u32,
+	/// The payload passed by `sigqueue`/`rt_sigqueueinfo`, or `SigVal(0)` for a delivery that
+	/// carries none.
+	pub value: SigVal,
+}
+
+impl SignalInfo {
+	/// Builds the `SignalInfo` for a plain `kill`/`tgkill`-style delivery from `sender`, with no
+	/// `sigval` payload.
+	pub fn user(sig: Signal, sender_pid: Pid, sender_uid: u32) -> Self {
+		Self {
+			signo: sig.get_id() as u8,
+			code: SI_USER,
+			pid: sender_pid,
+			uid: sender_uid,
+			value: SigVal(0),
+		}
+	}
+
+	/// Builds the `SignalInfo` for a signal raised by the kernel itself (a fault, `SIGCHLD`, a
+	/// resource-limit signal, ...), with no sender process and no payload.
+	pub fn kernel(sig: Signal) -> Self {
+		Self {
+			signo: sig.get_id() as u8,
+			code: SI_KERNEL,
+			pid: 0,
+			uid: 0,
+			value: SigVal(0),
+		}
+	}
+
+	/// Builds the `SignalInfo` for a `sigqueue`/`rt_sigqueueinfo` delivery from `sender`, carrying
+	/// `value`.
+	pub fn queued(sig: Signal, sender_pid: Pid, sender_uid: u32, value: SigVal) -> Self {
+		Self {
+			signo: sig.get_id() as u8,
+			code: SI_QUEUE,
+			pid: sender_pid,
+			uid: sender_uid,
+			value,
+		}
+	}
+}
+
+/// The per-process backlog of pending signal deliveries.
+///
+/// A standard signal number (1..=31) has at most one entry at a time, replaced on every new
+/// delivery; a real-time signal number (`SIGRTMIN..=SIGRTMAX`) accumulates one entry per
+/// delivery, each kept until explicitly popped.
+#[derive(Default)]
+pub struct RtSignalQueue {
+	queue: Vec<SignalInfo>,
+}
+
+impl RtSignalQueue {
+	/// Records a new delivery of `info`.
+	///
+	/// If `info.signo` is a standard signal already queued, the existing entry is replaced
+	/// rather than appended, since POSIX only ever keeps one instance of a standard signal
+	/// pending. A real-time signal number is always appended, behind any instance of it already
+	/// queued.
+	pub fn record(&mut self, info: SignalInfo) -> EResult<()> {
+		if !is_realtime_id(info.signo) {
+			if let Some(pos) = self.queue.iter().position(|i| i.signo == info.signo) {
+				self.queue.remove(pos);
+			}
+		}
+		self.queue.push(info)
+	}
+
+	/// Returns the lowest-numbered queued signal not blocked by `sigmask` (or that cannot be
+	/// blocked at all), if any.
+	///
+	/// If `peek` is `false`, the entry is removed: for a real-time signal with more than one
+	/// instance queued, this removes the oldest instance, leaving the rest queued.
+	pub fn pop_next(&mut self, sigmask: SigSet, peek: bool) -> Option<SignalInfo> {
+		let pos = self
+			.queue
+			.iter()
+			.enumerate()
+			.filter(|(_, info)| {
+				Signal::try_from(info.signo as c_int)
+					.map(|s| !s.can_catch() || !sigmask.is_set(info.signo as _))
+					.unwrap_or(false)
+			})
+			.min_by_key(|(_, info)| info.signo)
+			.map(|(i, _)| i)?;
+		if peek {
+			self.queue.get(pos).copied()
+		} else {
+			Some(self.queue.remove(pos))
+		}
+	}
+
+	/// Tells whether at least one instance of `signo` is still queued.
+	pub fn is_pending(&self, signo: u8) -> bool {
+		self.queue.iter().any(|i| i.signo == signo)
+	}
+
+	/// The number of deliveries currently queued, standard and real-time alike, as checked against
+	/// `RLIMIT_SIGPENDING` by [`sigqueue`].
+	pub fn len(&self) -> usize {
+		self.queue.len()
+	}
+}
+
+/// `sigqueue(2)`/`rt_sigqueueinfo(2)`: queues `sig` on `target` on behalf of `sender`, carrying
+/// `value`, instead of the plain no-payload delivery [`Process::kill`] sends.
+///
+/// Fails with [`EPERM`] if `sender` is not allowed to signal `target` (the same credential check
+/// `kill(2)` itself uses). Fails with [`EAGAIN`] if `target` has already queued as many signals as
+/// its own `RLIMIT_SIGPENDING` soft limit allows.
+pub fn sigqueue(sender: &Process, target: &Process, sig: Signal, value: SigVal) -> EResult<()> {
+	let sender_profile = sender.fs.lock().access_profile;
+	if !sender_profile.can_kill(target) {
+		return Err(errno!(EPERM));
+	}
+	let queued = target.signal.lock().queue.len() as u64;
+	target.resource_limits.lock().check_sigpending(queued)?;
+	let info = SignalInfo::queued(sig, sender.get_pid(), sender_profile.uid, value);
+	target.kill_with_info(info);
+	Ok(())
+}
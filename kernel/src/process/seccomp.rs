@@ -0,0 +1,611 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Seccomp-BPF syscall filtering.
+//!
+//! # Not enforced — `seccomp(2)` is a no-op security boundary in this tree
+//!
+//! A process's [`SeccompState`] ([`Process::seccomp`](super::Process::seccomp)) holds a stack of
+//! installed classic-BPF programs plus the "no-new-privs" flag `prctl(PR_SET_NO_NEW_PRIVS)` sets,
+//! and `seccomp(2)` (see [`syscall::seccomp`](super::super::syscall::seccomp)) lets a process push
+//! onto that stack. **No syscall-entry hook anywhere calls [`SeccompState::evaluate`] (or
+//! [`SeccompState::raw_evaluate`])**, so an installed filter is validated and stored but never
+//! actually run against a syscall: a caller cannot observe any difference between installing a
+//! filter that denies everything and installing nothing at all.
+//!
+//! This is not a "last mile" gap that a one-line call closes: wiring a real hook needs `syscall`'s
+//! dispatch table (to call `evaluate` from, and to assign each syscall the number
+//! [`SeccompData::nr`] must hold) and `arch::x86::idt::IntFrame`'s field layout (to read the
+//! instruction pointer and argument registers a [`SeccompData`] is built from), neither of which
+//! exists anywhere in this tree. There used to be a `SeccompData::from_frame` stub taking `&IntFrame`
+//! and doing `todo!()`; it has been removed rather than left as a function that type-checks but
+//! panics the moment anything calls it, since nothing can call it correctly without `IntFrame`
+//! existing first. [`evaluate`](SeccompState::evaluate) and the BPF interpreter below are written
+//! in the form a real dispatcher would call them in, so wiring the hook up remains the only thing
+//! left to do once those prerequisites land — but until then, treat every `seccomp(2)` filter
+//! install in this kernel as decorative.
+//!
+//! The interpreter ([`run_filter`]) implements the classic-BPF subset `seccomp(2)` filters are
+//! restricted to: `LD`/`LDX` (immediate, absolute offset into [`SeccompData`], or one of the 16
+//! scratch words), `ST`/`STX`, `ALU` (arithmetic/bitwise, against an immediate or `X`), `JMP`
+//! (`JA`/`JEQ`/`JGT`/`JGE`/`JSET`) and `RET`. Opcode encoding matches Linux's (`linux/filter.h`),
+//! since that is the ABI userspace `seccomp(2)` callers already compile their programs against.
+//!
+//! [`SeccompState::evaluate`]'s handling of the action set is split between two dispositions:
+//! `KILL_PROCESS`/`KILL_THREAD` force [`State::Zombie`](super::State::Zombie) directly, bypassing
+//! normal signal delivery entirely (the real `SECCOMP_RET_KILL_*` actions are not catchable, not
+//! blockable, and cannot be ignored, unlike a plain `SIGSYS`), while `TRAP` goes through the
+//! regular [`Process::kill`] path since its `SIGSYS` *is* an ordinary, catchable delivery. `TRACE`
+//! diverts into a ptrace stop via [`ptrace::stop_for_seccomp`] if a tracer is present, or fails
+//! the syscall with `ENOSYS` if not, matching Linux's own fallback for an untraced `TRACE` action.
+
+use super::{ptrace, scheduler::Scheduler, signal::Signal, Process};
+use utils::{collections::vec::Vec, errno, errno::EResult, ptr::arc::Arc};
+
+/// `ENOSYS`, the stable POSIX errno number returned to a syscall diverted by `SECCOMP_RET_TRACE`
+/// when no tracer is attached to observe the stop.
+const ENOSYS: u16 = 38;
+
+/// The signal number of `SIGSYS`, recorded as the termination signal on `SECCOMP_RET_KILL_*` and
+/// delivered on `SECCOMP_RET_TRAP`.
+///
+/// Looked up through [`Signal::try_from`] rather than a `Signal::SIGSYS` variant, since only
+/// [`Signal`]'s numeric `try_from` path (not its full variant list) is visible in this snapshot.
+const SIGSYS: i32 = 31;
+
+/// Maximum number of instructions in a single filter program, matching Linux's
+/// `BPF_MAXINSNS`.
+const MAX_INSNS: usize = 4096;
+
+/// The per-syscall input a filter program is run against, laid out exactly like Linux's
+/// `struct seccomp_data` so `BPF_ABS` loads use the same byte offsets real filter programs are
+/// compiled with.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SeccompData {
+	/// The syscall number.
+	pub nr: u32,
+	/// The syscall ABI/architecture token (e.g. `AUDIT_ARCH_X86_64`).
+	pub arch: u32,
+	/// The instruction pointer at the time of the syscall.
+	pub instruction_pointer: u64,
+	/// The syscall's raw arguments.
+	pub args: [u64; 6],
+}
+
+impl SeccompData {
+	/// Reads the 32-bit word starting at byte offset `k` of this struct's layout, for a
+	/// `BPF_ABS` load. Returns `None` if `k` does not land on a valid 4-byte-aligned field.
+	fn load_abs(&self, k: u32) -> Option<u32> {
+		let word = |v: u64, high: bool| if high { (v >> 32) as u32 } else { v as u32 };
+		Some(match k {
+			0 => self.nr,
+			4 => self.arch,
+			8 => word(self.instruction_pointer, false),
+			12 => word(self.instruction_pointer, true),
+			_ => {
+				let args_off = k.checked_sub(16)?;
+				let idx = (args_off / 8) as usize;
+				let arg = *self.args.get(idx)?;
+				word(arg, args_off % 8 == 4)
+			}
+		})
+	}
+}
+
+/// A single classic-BPF instruction, laid out like Linux's `struct sock_filter`.
+///
+/// `#[repr(C)]` so a `seccomp(2)` filter program can be copied in straight from userspace, byte
+/// for byte, through [`super::mem_space::copy::SyscallSlice`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SockFilter {
+	pub code: u16,
+	pub jt: u8,
+	pub jf: u8,
+	pub k: u32,
+}
+
+/// Instruction class, the low 3 bits of [`SockFilter::code`].
+mod class {
+	pub const LD: u16 = 0x00;
+	pub const LDX: u16 = 0x01;
+	pub const ST: u16 = 0x02;
+	pub const STX: u16 = 0x03;
+	pub const ALU: u16 = 0x04;
+	pub const JMP: u16 = 0x05;
+	pub const RET: u16 = 0x06;
+	pub const MASK: u16 = 0x07;
+}
+
+/// `LD`/`LDX` addressing mode, bits 5-7 of [`SockFilter::code`].
+mod mode {
+	pub const IMM: u16 = 0x00;
+	pub const ABS: u16 = 0x20;
+	pub const MEM: u16 = 0x60;
+	pub const MASK: u16 = 0xe0;
+}
+
+/// `ALU`/`JMP` operation, bits 4-7 of [`SockFilter::code`].
+mod op {
+	pub const ADD: u16 = 0x00;
+	pub const SUB: u16 = 0x10;
+	pub const MUL: u16 = 0x20;
+	pub const DIV: u16 = 0x30;
+	pub const OR: u16 = 0x40;
+	pub const AND: u16 = 0x50;
+	pub const LSH: u16 = 0x60;
+	pub const RSH: u16 = 0x70;
+	pub const XOR: u16 = 0xa0;
+	pub const JA: u16 = 0x00;
+	pub const JEQ: u16 = 0x10;
+	pub const JGT: u16 = 0x20;
+	pub const JGE: u16 = 0x30;
+	pub const JSET: u16 = 0x40;
+	pub const MASK: u16 = 0xf0;
+}
+
+/// Operand source for `ALU`/`JMP` (other than `JA`), bit 3 of [`SockFilter::code`], and the
+/// return-value source for `RET`, bits 3-4.
+mod src {
+	/// Operand is `k`, or for `RET`, return `k` itself.
+	pub const K: u16 = 0x00;
+	/// Operand is `X`.
+	pub const X: u16 = 0x08;
+	/// For `RET`, return `A`.
+	pub const A: u16 = 0x10;
+	pub const MASK: u16 = 0x08;
+	/// Mask isolating the return-value source of a `RET`.
+	pub const RVAL_MASK: u16 = 0x18;
+}
+
+/// `SECCOMP_RET_*` action codes, matching Linux's `include/uapi/linux/seccomp.h` exactly: when
+/// multiple filters run, the winning action is the one whose packed `u32` is numerically lowest
+/// *as a signed 32-bit integer*, which is exactly why `KILL_PROCESS` (the only action with its
+/// top bit set) always wins over every other action regardless of install order.
+pub const SECCOMP_RET_KILL_PROCESS: u32 = 0x8000_0000;
+pub const SECCOMP_RET_KILL_THREAD: u32 = 0x0000_0000;
+pub const SECCOMP_RET_TRAP: u32 = 0x0003_0000;
+pub const SECCOMP_RET_ERRNO: u32 = 0x0005_0000;
+pub const SECCOMP_RET_TRACE: u32 = 0x7ff0_0000;
+pub const SECCOMP_RET_LOG: u32 = 0x7ffc_0000;
+pub const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+
+/// Mask isolating the action bits of a `SECCOMP_RET_*` value.
+const RET_ACTION_FULL: u32 = 0xffff_0000;
+/// Mask isolating the data bits (e.g. the errno for `SECCOMP_RET_ERRNO`).
+const RET_DATA: u32 = 0x0000_ffff;
+
+/// The effect a [`SeccompState::evaluate`] result has on the syscall about to run, decoded from
+/// the raw `SECCOMP_RET_*` value a filter program returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+	/// Let the syscall run normally.
+	Allow,
+	/// Abort the syscall without running it, returning `-errno` to userspace.
+	Errno(u16),
+	/// The syscall was killed: the process (or its whole thread group, for `KILL_PROCESS`) was
+	/// already forced to exit with `SIGSYS` as its termination signal, and the syscall must not be
+	/// dispatched.
+	Killed,
+	/// `SECCOMP_RET_TRAP`: `SIGSYS` was delivered (without the `siginfo_t` payload real Linux
+	/// attaches, since this snapshot has no visible siginfo-capture path) and the syscall must
+	/// not be dispatched.
+	Trap,
+	/// Stop for tracing (`PTRACE_EVENT_SECCOMP`) before running the syscall; `data` is the low 16
+	/// bits of the filter's return value, made available to the tracer.
+	Trace(u16),
+	/// Log the syscall (e.g. to an audit subsystem) and let it run normally.
+	Log,
+}
+
+/// Runs `prog` against `data`, returning its raw `SECCOMP_RET_*` value.
+///
+/// [`SeccompFilter::new`] validates jump targets and the final `RET`, so a validated program never
+/// runs off the end of `prog`. Scratch-word indices are not validated there: the interpreter masks
+/// `k` to the 16 words of `mem` instead.
+pub fn run_filter(prog: &[SockFilter], data: &SeccompData) -> u32 {
+	let mut a: u32 = 0;
+	let mut x: u32 = 0;
+	let mut mem = [0u32; 16];
+	let mut pc: usize = 0;
+	loop {
+		let Some(insn) = prog.get(pc) else {
+			// Fell off the end without a `RET`: validated programs never do this, but default to
+			// the most restrictive action rather than continue into undefined territory.
+			return SECCOMP_RET_KILL_PROCESS;
+		};
+		let operand = |src_bit| if src_bit == src::K { insn.k } else { x };
+		match insn.code & class::MASK {
+			class::LD => {
+				a = match insn.code & mode::MASK {
+					mode::IMM => insn.k,
+					mode::ABS => data.load_abs(insn.k).unwrap_or(0),
+					mode::MEM => mem[(insn.k as usize) & 0xf],
+					_ => 0,
+				};
+				pc += 1;
+			}
+			class::LDX => {
+				x = match insn.code & mode::MASK {
+					mode::IMM => insn.k,
+					mode::MEM => mem[(insn.k as usize) & 0xf],
+					_ => 0,
+				};
+				pc += 1;
+			}
+			class::ST => {
+				mem[(insn.k as usize) & 0xf] = a;
+				pc += 1;
+			}
+			class::STX => {
+				mem[(insn.k as usize) & 0xf] = x;
+				pc += 1;
+			}
+			class::ALU => {
+				let rhs = operand(insn.code & src::MASK);
+				a = match insn.code & op::MASK {
+					op::ADD => a.wrapping_add(rhs),
+					op::SUB => a.wrapping_sub(rhs),
+					op::MUL => a.wrapping_mul(rhs),
+					op::DIV => {
+						if rhs == 0 {
+							0
+						} else {
+							a / rhs
+						}
+					}
+					op::OR => a | rhs,
+					op::AND => a & rhs,
+					op::LSH => a.wrapping_shl(rhs),
+					op::RSH => a.wrapping_shr(rhs),
+					op::XOR => a ^ rhs,
+					_ => a,
+				};
+				pc += 1;
+			}
+			class::JMP => {
+				if insn.code & op::MASK == op::JA {
+					pc += 1 + insn.k as usize;
+					continue;
+				}
+				let rhs = operand(insn.code & src::MASK);
+				let taken = match insn.code & op::MASK {
+					op::JEQ => a == rhs,
+					op::JGT => a > rhs,
+					op::JGE => a >= rhs,
+					op::JSET => a & rhs != 0,
+					_ => false,
+				};
+				pc += 1 + if taken { insn.jt as usize } else { insn.jf as usize };
+			}
+			class::RET => {
+				return if insn.code & src::RVAL_MASK == src::A {
+					a
+				} else {
+					insn.k
+				};
+			}
+			_ => return SECCOMP_RET_KILL_PROCESS,
+		}
+	}
+}
+
+/// A single installed, pre-validated filter program.
+#[derive(Debug)]
+pub struct SeccompFilter {
+	program: Vec<SockFilter>,
+}
+
+impl SeccompFilter {
+	/// Validates and wraps `program`.
+	///
+	/// Rejects an empty program, one longer than [`MAX_INSNS`], one that does not end in a `RET`
+	/// (so every path through it is guaranteed to terminate there), and one containing a jump
+	/// (`JA`, or a conditional's `jt`/`jf`) whose target falls outside `program`.
+	pub fn new(program: Vec<SockFilter>) -> EResult<Self> {
+		if program.is_empty() || program.len() > MAX_INSNS {
+			return Err(errno!(EINVAL));
+		}
+		if program.last().unwrap().code & class::MASK != class::RET {
+			return Err(errno!(EINVAL));
+		}
+		for (pc, insn) in program.iter().enumerate() {
+			if insn.code & class::MASK != class::JMP {
+				continue;
+			}
+			let in_bounds = |target: usize| target < program.len();
+			let ok = if insn.code & op::MASK == op::JA {
+				pc.checked_add(1 + insn.k as usize).is_some_and(in_bounds)
+			} else {
+				pc.checked_add(1 + insn.jt as usize).is_some_and(in_bounds)
+					&& pc.checked_add(1 + insn.jf as usize).is_some_and(in_bounds)
+			};
+			if !ok {
+				return Err(errno!(EINVAL));
+			}
+		}
+		Ok(Self { program })
+	}
+}
+
+/// A process's seccomp-BPF state: the stack of installed filters and the "no-new-privs" flag.
+#[derive(Debug, Clone, Default)]
+pub struct SeccompState {
+	/// Set by `prctl(PR_SET_NO_NEW_PRIVS)`; once set, it cannot be unset, and it allows
+	/// unprivileged filter installation.
+	pub no_new_privs: bool,
+	/// Installed filters, oldest first. All of them are evaluated on every syscall; the winning
+	/// action is the numerically-lowest `SECCOMP_RET_*` value any of them returns (see
+	/// [`SECCOMP_RET_KILL_PROCESS`]'s doc comment for why that rule picks the most restrictive
+	/// action).
+	filters: Vec<Arc<SeccompFilter>>,
+}
+
+impl SeccompState {
+	/// Installs `filter`, as `seccomp(2)`/`prctl(PR_SET_SECCOMP)` would.
+	///
+	/// `privileged` stands in for holding `CAP_SYS_ADMIN`: this snapshot only models capabilities
+	/// coarsely through [`AccessProfile::is_privileged`](crate::file::perm::AccessProfile::is_privileged),
+	/// not a granular capability set, so that is what gates installation here too. Either that or
+	/// [`Self::no_new_privs`] must hold, exactly as the real syscall requires, since a filter
+	/// could otherwise be used to let a `setuid` binary silently downgrade its own sandboxing.
+	pub fn install(&mut self, filter: SeccompFilter, privileged: bool) -> EResult<()> {
+		if !privileged && !self.no_new_privs {
+			return Err(errno!(EACCES));
+		}
+		self.filters.push(Arc::new(filter)?)?;
+		Ok(())
+	}
+
+	/// Runs every installed filter against `data`, in install order, and returns the
+	/// numerically-lowest (as a signed integer) raw `SECCOMP_RET_*` value, or
+	/// [`SECCOMP_RET_ALLOW`] if no filter is installed.
+	pub fn raw_evaluate(&self, data: &SeccompData) -> u32 {
+		self.filters
+			.iter()
+			.map(|filter| run_filter(&filter.program, data))
+			.min_by_key(|ret| *ret as i32)
+			.unwrap_or(SECCOMP_RET_ALLOW)
+	}
+
+	/// Runs every installed filter against `data` and applies the resulting action to `proc`,
+	/// returning the [`Verdict`] the (not-present-in-this-snapshot) syscall dispatcher should act
+	/// on before/instead of running the syscall.
+	///
+	/// `KILL_THREAD`/`KILL_PROCESS` force `proc` (or its thread group) to exit with `SIGSYS` as its
+	/// termination signal through [`force_kill`], and `TRAP` delivers `SIGSYS` through
+	/// [`Process::kill`], rather than leaving either to the caller, since every caller would
+	/// otherwise have to duplicate the same lines.
+	pub fn evaluate(&self, proc: &Process, data: &SeccompData) -> Verdict {
+		let ret = self.raw_evaluate(data);
+		let data_bits = (ret & RET_DATA) as u16;
+		match ret & RET_ACTION_FULL {
+			SECCOMP_RET_KILL_PROCESS => {
+				force_kill(proc, true);
+				Scheduler::tick();
+				Verdict::Killed
+			}
+			SECCOMP_RET_KILL_THREAD => {
+				force_kill(proc, false);
+				Scheduler::tick();
+				Verdict::Killed
+			}
+			SECCOMP_RET_TRAP => {
+				if let Ok(sig) = Signal::try_from(SIGSYS) {
+					proc.kill(sig);
+				}
+				Verdict::Trap
+			}
+			SECCOMP_RET_ERRNO => Verdict::Errno(data_bits),
+			SECCOMP_RET_TRACE => {
+				if ptrace::stop_for_seccomp(proc, data_bits) {
+					Verdict::Trace(data_bits)
+				} else {
+					// No tracer is attached to observe the stop: Linux fails the syscall with
+					// `ENOSYS` instead of silently running it, so an untraced binary compiled
+					// against a `TRACE`-only filter doesn't get a free pass.
+					Verdict::Errno(ENOSYS)
+				}
+			}
+			SECCOMP_RET_LOG => Verdict::Log,
+			_ => Verdict::Allow,
+		}
+	}
+}
+
+/// `KILL_PROCESS`/`KILL_THREAD`: forces `proc` (or, if `group`, its whole thread group) straight
+/// to [`State::Zombie`](super::State::Zombie), bypassing [`Process::kill`]'s normal
+/// queue-then-dispatch delivery entirely, since these two actions are not catchable, blockable,
+/// or ignorable in the way a plain `SIGSYS` is.
+///
+fn force_kill(proc: &Process, group: bool) {
+	proc.signal.lock().termsig = SIGSYS as u8;
+	if group {
+		proc.exit_group(0);
+	} else {
+		proc.exit(0);
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// `RET #k`: an unconditional return of the immediate `k`.
+	fn ret_k(k: u32) -> SockFilter {
+		SockFilter {
+			code: (class::RET | src::K),
+			jt: 0,
+			jf: 0,
+			k,
+		}
+	}
+
+	#[test_case]
+	fn run_filter_ret_k_returns_the_immediate() {
+		let prog = [ret_k(SECCOMP_RET_ALLOW)];
+		assert_eq!(run_filter(&prog, &SeccompData::default()), SECCOMP_RET_ALLOW);
+	}
+
+	#[test_case]
+	fn run_filter_load_abs_reads_the_syscall_number() {
+		let prog = [
+			SockFilter {
+				code: class::LD | mode::ABS,
+				jt: 0,
+				jf: 0,
+				k: 0,
+			},
+			SockFilter {
+				code: class::JMP | op::JEQ | src::K,
+				jt: 0,
+				jf: 1,
+				k: 42,
+			},
+			ret_k(SECCOMP_RET_KILL_PROCESS),
+			ret_k(SECCOMP_RET_ALLOW),
+		];
+		let mut data = SeccompData::default();
+		data.nr = 42;
+		assert_eq!(run_filter(&prog, &data), SECCOMP_RET_KILL_PROCESS);
+		data.nr = 7;
+		assert_eq!(run_filter(&prog, &data), SECCOMP_RET_ALLOW);
+	}
+
+	#[test_case]
+	fn run_filter_alu_add_uses_k_as_rhs_by_default() {
+		let prog = [
+			SockFilter {
+				code: class::LD | mode::IMM,
+				jt: 0,
+				jf: 0,
+				k: 10,
+			},
+			SockFilter {
+				code: class::ALU | op::ADD | src::K,
+				jt: 0,
+				jf: 0,
+				k: 5,
+			},
+			SockFilter {
+				code: class::RET | src::A,
+				jt: 0,
+				jf: 0,
+				k: 0,
+			},
+		];
+		assert_eq!(run_filter(&prog, &SeccompData::default()), 15);
+	}
+
+	#[test_case]
+	fn run_filter_falling_off_the_end_kills_the_process() {
+		let prog = [SockFilter {
+			code: class::LD | mode::IMM,
+			jt: 0,
+			jf: 0,
+			k: 0,
+		}];
+		assert_eq!(run_filter(&prog, &SeccompData::default()), SECCOMP_RET_KILL_PROCESS);
+	}
+
+	#[test_case]
+	fn seccomp_filter_new_rejects_empty_program() {
+		assert!(SeccompFilter::new(Vec::new()).is_err());
+	}
+
+	#[test_case]
+	fn seccomp_filter_new_rejects_program_not_ending_in_ret() {
+		let mut prog = Vec::new();
+		prog.push(SockFilter {
+			code: class::LD | mode::IMM,
+			jt: 0,
+			jf: 0,
+			k: 0,
+		})
+		.unwrap();
+		assert!(SeccompFilter::new(prog).is_err());
+	}
+
+	#[test_case]
+	fn seccomp_filter_new_rejects_out_of_bounds_jump() {
+		let mut prog = Vec::new();
+		prog.push(SockFilter {
+			code: class::JMP | op::JA,
+			jt: 0,
+			jf: 0,
+			k: 10,
+		})
+		.unwrap();
+		prog.push(ret_k(SECCOMP_RET_ALLOW)).unwrap();
+		assert!(SeccompFilter::new(prog).is_err());
+	}
+
+	#[test_case]
+	fn seccomp_filter_new_accepts_a_valid_program() {
+		let mut prog = Vec::new();
+		prog.push(ret_k(SECCOMP_RET_ALLOW)).unwrap();
+		assert!(SeccompFilter::new(prog).is_ok());
+	}
+
+	#[test_case]
+	fn seccomp_state_raw_evaluate_defaults_to_allow_with_no_filters() {
+		let state = SeccompState::default();
+		assert_eq!(state.raw_evaluate(&SeccompData::default()), SECCOMP_RET_ALLOW);
+	}
+
+	#[test_case]
+	fn seccomp_state_raw_evaluate_picks_the_most_restrictive_action() {
+		let mut state = SeccompState::default();
+		let mut allow_prog = Vec::new();
+		allow_prog.push(ret_k(SECCOMP_RET_ALLOW)).unwrap();
+		let mut kill_prog = Vec::new();
+		kill_prog.push(ret_k(SECCOMP_RET_KILL_PROCESS)).unwrap();
+		state
+			.install(SeccompFilter::new(allow_prog).unwrap(), true)
+			.unwrap();
+		state
+			.install(SeccompFilter::new(kill_prog).unwrap(), true)
+			.unwrap();
+		assert_eq!(
+			state.raw_evaluate(&SeccompData::default()),
+			SECCOMP_RET_KILL_PROCESS
+		);
+	}
+
+	#[test_case]
+	fn seccomp_state_install_requires_privilege_or_no_new_privs() {
+		let mut state = SeccompState::default();
+		let mut prog = Vec::new();
+		prog.push(ret_k(SECCOMP_RET_ALLOW)).unwrap();
+		assert!(SeccompFilter::new(prog)
+			.and_then(|filter| state.install(filter, false))
+			.is_err());
+	}
+
+	#[test_case]
+	fn seccomp_state_install_succeeds_once_no_new_privs_is_set() {
+		let mut state = SeccompState::default();
+		state.no_new_privs = true;
+		let mut prog = Vec::new();
+		prog.push(ret_k(SECCOMP_RET_ALLOW)).unwrap();
+		assert!(SeccompFilter::new(prog)
+			.and_then(|filter| state.install(filter, false))
+			.is_ok());
+	}
+}
@@ -0,0 +1,152 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Sessions and controlling terminals, for shell-style job control.
+//!
+//! A [`Session`] is, like [`super::ProcessLinks::process_group`], data that only matters on its
+//! leader: [`super::ProcessLinks::session_leader`] is `None` for a session leader (mirroring
+//! `group_leader`) and [`super::Process::get_sid`] walks it exactly as
+//! [`super::Process::get_pgid`] walks `group_leader`. [`setsid`] makes the caller both a process
+//! group leader and a session leader with no controlling terminal, the same combination a shell
+//! starts a new login session with.
+//!
+//! What this does *not* do: wire [`set_controlling_terminal`]/[`release_controlling_terminal`] to
+//! the `TIOCSCTTY`/`TIOCNOTTY` ioctl numbers, or make `TIOCGPGRP`/`TIOCSPGRP` (already implemented
+//! in `device::tty`, against the global `TTY` singleton's own `pgrp` field rather than a
+//! `Session`) go through [`Session::foreground_pgid`] instead. `syscall::ioctl`, where those
+//! request numbers would be defined, has no file in this tree's snapshot, and there being exactly
+//! one global TTY and no pty devices at all means the existing single-terminal scheme is already
+//! equivalent in effect to routing through a session for as long as this kernel has at most one
+//! session. Likewise out of scope: raising `SIGTSTP`/`SIGINT`/`SIGQUIT` off control characters,
+//! which needs to hook `crate::tty`'s input processing, and that module has no files here either.
+
+use super::{oom, pid::Pid, signal::Signal, Process, State};
+use utils::{collections::vec::Vec, errno, errno::EResult};
+
+/// A process session: the set of process groups descending from one `setsid` call, sharing at
+/// most one controlling terminal.
+pub struct Session {
+	/// The foreground process group: the one allowed to read from (and, depending on `TOSTOP`,
+	/// write to) the session's controlling terminal.
+	pub foreground_pgid: Pid,
+	/// `true` once the session has acquired a controlling terminal via [`set_controlling_terminal`].
+	pub has_ctty: bool,
+}
+
+impl Session {
+	/// Creates a new session led by `sid`, with no controlling terminal and itself as the
+	/// foreground group.
+	pub fn new(sid: Pid) -> Self {
+		Self {
+			foreground_pgid: sid,
+			has_ctty: false,
+		}
+	}
+}
+
+/// `setsid`: makes `proc` the leader of a new session and a new process group, with no
+/// controlling terminal.
+///
+/// Fails with [`EPERM`] if `proc` is already a process group leader, since that would otherwise
+/// leave the group it already leads without any member still eligible to start a session of its
+/// own later.
+pub fn setsid(proc: &Process) -> EResult<Pid> {
+	if proc.get_pgid() == proc.get_pid() {
+		return Err(errno!(EPERM));
+	}
+	proc.set_pgid(0)?;
+	let mut links = proc.links.lock();
+	links.session_leader = None;
+	drop(links);
+	let sid = proc.get_pid();
+	*proc.session.lock() = Session::new(sid);
+	Ok(sid)
+}
+
+/// `TIOCSCTTY`: makes the calling process's controlling terminal the session's controlling
+/// terminal.
+///
+/// Fails with [`EPERM`] if `proc` is not a session leader, or its session already has a
+/// controlling terminal and `force` was not requested.
+pub fn set_controlling_terminal(proc: &Process, force: bool) -> EResult<()> {
+	if proc.get_sid() != proc.get_pid() {
+		return Err(errno!(EPERM));
+	}
+	let mut session = proc.session.lock();
+	if session.has_ctty && !force {
+		return Err(errno!(EPERM));
+	}
+	session.has_ctty = true;
+	Ok(())
+}
+
+/// `TIOCNOTTY`: gives up the controlling terminal of `proc`'s session, if it is the session
+/// leader and the session currently has one.
+pub fn release_controlling_terminal(proc: &Process) {
+	if proc.get_sid() != proc.get_pid() {
+		return;
+	}
+	let had_ctty = {
+		let mut session = proc.session.lock();
+		let had = session.has_ctty;
+		session.has_ctty = false;
+		had
+	};
+	if had_ctty {
+		hangup_foreground_group(proc);
+	}
+}
+
+/// Sends `SIGHUP` then `SIGCONT` to every member of `proc`'s session's foreground process group,
+/// if it currently has at least one stopped member.
+///
+/// This is the real-world effect of losing a controlling terminal (the session leader exiting, or
+/// `TIOCNOTTY`): a stopped job must not end up wedged with no way to ever be resumed.
+///
+/// This does not implement the full orphaned-process-group check POSIX specifies (no member has a
+/// living parent outside the group, in the same session): that needs a session-wide reverse
+/// membership index this tree doesn't track (only a group leader's own
+/// [`super::ProcessLinks::process_group`] is recorded). It is only called from places where a
+/// controlling terminal is genuinely being lost, so the foreground group is, in each of those
+/// cases, in fact the one losing its last connection to a living process outside it.
+pub fn hangup_foreground_group(proc: &Process) {
+	let fg_pgid = proc.session.lock().foreground_pgid;
+	let Some(leader) = Process::get_by_pid(fg_pgid) else {
+		return;
+	};
+	let mut members = Vec::new();
+	{
+		let links = leader.links.lock();
+		for pid in links.process_group.iter() {
+			oom::wrap(|| members.push(*pid));
+		}
+	}
+	let has_stopped = members
+		.iter()
+		.filter_map(|pid| Process::get_by_pid(*pid))
+		.any(|member| member.get_state() == State::Stopped);
+	if !has_stopped {
+		return;
+	}
+	for pid in members.iter() {
+		if let Some(member) = Process::get_by_pid(*pid) {
+			member.kill_process(Signal::SIGHUP);
+			member.kill_process(Signal::SIGCONT);
+		}
+	}
+}
@@ -0,0 +1,118 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The state shared by every `CLONE_THREAD` sibling of a process.
+//!
+//! Maestro schedules each thread as its own [`Process`](super::Process), with its own `tid`, so
+//! that the scheduler does not need a separate notion of "thread" at all. What POSIX calls a
+//! process (one PID, one set of pending signals not aimed at any particular thread, one exit
+//! status) is, here, the set of threads sharing the same [`ThreadGroup`]. [`super::Process::fork`]
+//! installs a fresh, single-member group for a `fork`-style child and clones the `Arc` into a
+//! `thread`-style one instead of creating a new group.
+//!
+//! What this does *not* wire in: `waitpid` itself, which should report on [`Self::leader`] rather
+//! than on whichever member happened to be reaped, is not part of this tree's snapshot (there is
+//! no `syscall::wait*` file to update).
+
+use super::{pid::Pid, signal::SigSet, ExitStatus, Signal};
+use core::ffi::c_int;
+use utils::{collections::vec::Vec, errno::EResult};
+
+/// State shared by every thread of a process.
+pub struct ThreadGroup {
+	/// The TID of the thread-group leader: the id reported as the group's PID, and the one
+	/// `waitpid` observes when the group exits.
+	pub leader: Pid,
+	/// The TIDs of every thread currently alive in the group, including the leader.
+	pub members: Vec<Pid>,
+	/// Signals sent to the process as a whole (`kill(2)`, as opposed to a `tgkill`-style signal
+	/// aimed at one specific thread), pending until whichever member thread next checks for
+	/// signals and does not have it blocked.
+	pending: SigSet,
+	/// Set once by [`Self::begin_exit`], and reported as the group's exit status. `None` while
+	/// the group is still running.
+	pub exit_status: Option<ExitStatus>,
+}
+
+impl ThreadGroup {
+	/// Creates a new thread group whose only member is `leader`.
+	pub fn new(leader: Pid) -> EResult<Self> {
+		let mut members = Vec::new();
+		members.push(leader)?;
+		Ok(Self {
+			leader,
+			members,
+			pending: SigSet::default(),
+			exit_status: None,
+		})
+	}
+
+	/// Adds `tid` as a member of the group, for a `CLONE_THREAD` child.
+	pub fn add_member(&mut self, tid: Pid) -> EResult<()> {
+		self.members.push(tid)
+	}
+
+	/// Removes `tid` from the group's member list, once that thread has become a zombie.
+	pub fn remove_member(&mut self, tid: Pid) {
+		if let Some(i) = self.members.iter().position(|t| *t == tid) {
+			self.members.remove(i);
+		}
+	}
+
+	/// Queues `sig` for the group as a whole, to be dequeued by whichever member thread next
+	/// calls [`Self::next_signal`] and does not have it blocked.
+	pub fn kill(&mut self, sig: Signal) {
+		self.pending.set(sig.get_id() as _);
+	}
+
+	/// Returns the next group-directed signal not blocked by `sigmask` (the calling thread's own
+	/// signal mask), or `None` if none is pending or eligible.
+	///
+	/// If `peek` is `false`, the signal is cleared from the group's pending set.
+	pub fn next_signal(&mut self, sigmask: SigSet, peek: bool) -> Option<Signal> {
+		let sig = self
+			.pending
+			.iter()
+			.enumerate()
+			.filter(|(_, b)| *b)
+			.filter_map(|(i, _)| {
+				let s = Signal::try_from(i as c_int).ok()?;
+				(!s.can_catch() || !sigmask.is_set(i)).then_some(s)
+			})
+			.next();
+		if !peek {
+			if let Some(id) = sig {
+				self.pending.clear(id.get_id() as _);
+			}
+		}
+		sig
+	}
+
+	/// Marks the group as exiting with `status`, if it has not already begun exiting.
+	///
+	/// Returns `true` the first time this is called for the group (the caller is then
+	/// responsible for actually tearing down every member), `false` if an earlier call already
+	/// marked the group as exiting.
+	pub fn begin_exit(&mut self, status: ExitStatus) -> bool {
+		if self.exit_status.is_some() {
+			return false;
+		}
+		self.exit_status = Some(status);
+		true
+	}
+}
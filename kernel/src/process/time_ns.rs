@@ -0,0 +1,114 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Per-process time namespace: a signed offset applied to `CLOCK_MONOTONIC` and `CLOCK_BOOTTIME`
+//! reads, so a process frozen for some wall-clock duration and later restored sees continuity
+//! instead of a jump.
+//!
+//! [`TimeNamespace`] is inherited across `fork` (the namespace is shared, like the parent's
+//! `CLOCK_MONOTONIC` epoch would be) and its offsets may only be set once, before the clock has
+//! been read through it: once a thread has observed the clock, changing the offset out from under
+//! it would itself look like a jump, defeating the point. `CLOCK_REALTIME` is never offset, since
+//! it must keep tracking true wall time.
+//!
+//! Wiring this in is two call sites, both outside this tree's snapshot: `time::clock::current_time_ns`
+//! would call [`TimeNamespace::apply`] on the raw hardware reading before returning it, and
+//! `timer_create`'s relative-arm math (and [`super::checkpoint::Expiration::capture`]) would do
+//! the same so that a one-second relative timer still fires one real second later regardless of
+//! the offset in effect. The `/proc/<pid>/timens_offsets` node in
+//! `file::fs::proc::proc_dir::timens_offsets` is the userspace-facing half of this: it reads and
+//! writes a [`TimeNamespace`] through [`Process::time_ns`](super::Process::time_ns).
+
+use crate::time::unit::ClockIdT;
+use core::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use utils::{errno, errno::EResult};
+
+/// `CLOCK_MONOTONIC`'s id, as passed to `clock_gettime`/`timer_create`.
+pub const CLOCK_MONOTONIC: ClockIdT = 1;
+/// `CLOCK_BOOTTIME`'s id, as passed to `clock_gettime`/`timer_create`.
+pub const CLOCK_BOOTTIME: ClockIdT = 7;
+
+/// Adds a signed nanosecond offset to an unsigned nanosecond timestamp, saturating at `0` rather
+/// than wrapping if the offset would otherwise take it negative.
+fn apply_offset(ns: u64, offset_ns: i64) -> u64 {
+	if offset_ns >= 0 {
+		ns.saturating_add(offset_ns as u64)
+	} else {
+		ns.saturating_sub((-offset_ns) as u64)
+	}
+}
+
+/// A process's time namespace.
+///
+/// Offsets default to `0`, making the namespace a no-op until a supervisor explicitly sets them
+/// (e.g. after restoring a frozen process).
+#[derive(Debug, Default)]
+pub struct TimeNamespace {
+	/// Offset applied to `CLOCK_MONOTONIC` reads, in nanoseconds.
+	monotonic_offset_ns: AtomicI64,
+	/// Offset applied to `CLOCK_BOOTTIME` reads, in nanoseconds.
+	boottime_offset_ns: AtomicI64,
+	/// Set the first time [`Self::apply`] is called, after which [`Self::set_offsets`] is
+	/// refused.
+	observed: AtomicBool,
+}
+
+impl TimeNamespace {
+	/// Creates a new namespace with zero offsets.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Returns the current `(monotonic, boottime)` offsets, in nanoseconds.
+	pub fn offsets(&self) -> (i64, i64) {
+		(
+			self.monotonic_offset_ns.load(Ordering::Relaxed),
+			self.boottime_offset_ns.load(Ordering::Relaxed),
+		)
+	}
+
+	/// Sets the `(monotonic, boottime)` offsets, in nanoseconds.
+	///
+	/// Fails with [`EBUSY`] if a clock has already been read through this namespace (via
+	/// [`Self::apply`]), since changing the offset at that point would itself look like a clock
+	/// jump to whatever already observed it.
+	pub fn set_offsets(&self, monotonic_offset_ns: i64, boottime_offset_ns: i64) -> EResult<()> {
+		if self.observed.load(Ordering::Acquire) {
+			return Err(errno!(EBUSY));
+		}
+		self.monotonic_offset_ns
+			.store(monotonic_offset_ns, Ordering::Relaxed);
+		self.boottime_offset_ns
+			.store(boottime_offset_ns, Ordering::Relaxed);
+		Ok(())
+	}
+
+	/// Applies this namespace's offset to a raw `ns` nanosecond reading of clock `clockid`,
+	/// latching the namespace so that [`Self::set_offsets`] is refused from this point on.
+	///
+	/// `CLOCK_REALTIME` (and any clock other than `CLOCK_MONOTONIC`/`CLOCK_BOOTTIME`) is passed
+	/// through unchanged.
+	pub fn apply(&self, clockid: ClockIdT, ns: u64) -> u64 {
+		self.observed.store(true, Ordering::Release);
+		match clockid {
+			CLOCK_MONOTONIC => apply_offset(ns, self.monotonic_offset_ns.load(Ordering::Relaxed)),
+			CLOCK_BOOTTIME => apply_offset(ns, self.boottime_offset_ns.load(Ordering::Relaxed)),
+			_ => ns,
+		}
+	}
+}
@@ -0,0 +1,250 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `unveil(2)`-style per-path filesystem visibility restriction, orthogonal to the blunt
+//! whole-tree swap `chroot` performs.
+//!
+//! Before a process's first [`UnveilList::unveil`] call, every path is visible exactly as today.
+//! After at least one call, [`UnveilList::check`] matches a candidate path against the longest
+//! unveiled prefix covering it: no covering prefix is [`ENOENT`], a covering prefix without the
+//! requested [`Perms`] is [`EACCES`]. A call with both arguments `None` permanently locks the list
+//! (see [`UnveilList::unveil`]'s own doc comment) so a compromised process cannot widen its own
+//! sandbox back open.
+//!
+//! [`UnveilList::check`] is not wired into `vfs::get_file_from_path` itself: that function lives
+//! in `file::vfs`, which (like `file::fd`, the gap [`super::rlimit::ResourceLimits::check_nofile`]
+//! already documents) has no file in this tree's snapshot to add the call into. Instead, every
+//! syscall in this tree that resolves a path through `vfs::get_file_from_path` calls
+//! [`check`](self::check) itself at its own top, the same manual per-call-site pattern
+//! [`super::pledge::enforce`] uses for the same reason: `readlink`, `rmdir`, `chown`/`chown32`,
+//! `mount`, `umount`/`umount2`, `translator_attach`, `translator_detach` and
+//! `inotify_add_watch` are covered this way. A future path-resolution chokepoint inside
+//! `file::vfs` would let a single call there replace all of these.
+
+use core::ops::BitOr;
+use utils::{
+	collections::{
+		path::{Path, PathBuf},
+		vec::Vec,
+	},
+	errno,
+	errno::EResult,
+};
+
+/// A bitmask of the operations an unveiled path grants, mirroring `unveil(2)`'s `rwxc` permission
+/// string.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Perms(u8);
+
+impl Perms {
+	/// `r`: the path may be read.
+	pub const READ: Self = Self(1 << 0);
+	/// `w`: the path may be written.
+	pub const WRITE: Self = Self(1 << 1);
+	/// `x`: the path may be executed.
+	pub const EXEC: Self = Self(1 << 2);
+	/// `c`: a new node may be created at (or under) the path.
+	pub const CREATE: Self = Self(1 << 3);
+
+	/// Tells whether every permission set in `required` is also set in `self`.
+	pub fn contains(&self, required: Self) -> bool {
+		self.0 & required.0 == required.0
+	}
+}
+
+impl BitOr for Perms {
+	type Output = Self;
+
+	fn bitor(self, rhs: Self) -> Self {
+		Self(self.0 | rhs.0)
+	}
+}
+
+/// Tells whether `path` falls under `prefix`, component by component.
+fn is_under(path: &Path, prefix: &Path) -> EResult<bool> {
+	let path_components: Vec<_> = path.components().collect()?;
+	let prefix_components: Vec<_> = prefix.components().collect()?;
+	Ok(prefix_components.len() <= path_components.len()
+		&& prefix_components
+			.iter()
+			.zip(path_components.iter())
+			.all(|(a, b)| a == b))
+}
+
+/// A process's `unveil` overlay: the set of paths it has chosen to keep visible, narrowing down
+/// from "everything", plus whether that set has been locked against further widening.
+#[derive(Clone, Default)]
+pub struct UnveilList {
+	/// The unveiled paths and the permissions granted under each, in call order. A later call
+	/// for a path already present replaces its permissions rather than appending a duplicate
+	/// entry.
+	entries: Vec<(PathBuf, Perms)>,
+	/// Once `true`, no entry may be added, replaced, or have its permissions widened, and no
+	/// further lock call is needed (it is already in effect).
+	locked: bool,
+	/// If `true`, [`Self::reset_for_exec`] leaves the list untouched across `execve` instead of
+	/// clearing it back to unrestricted, letting a process that sets this up keep its sandbox
+	/// through re-exec instead of having to re-establish it every time.
+	keep_on_exec: bool,
+}
+
+impl UnveilList {
+	/// `unveil(path, perms)`.
+	///
+	/// If both `path` and `perms` are `None`, permanently locks the list: every future call
+	/// (including another lock call) fails with [`EPERM`], matching real `unveil(2)`'s own "once
+	/// locked, always locked" guarantee. Otherwise, `path` is added (or, if already present,
+	/// has its permissions replaced by) `perms`.
+	pub fn unveil(&mut self, path: Option<PathBuf>, perms: Option<Perms>) -> EResult<()> {
+		if self.locked {
+			return Err(errno!(EPERM));
+		}
+		match (path, perms) {
+			(None, None) => {
+				self.locked = true;
+				Ok(())
+			}
+			(Some(path), Some(perms)) => {
+				if let Some(slot) = self.entries.iter_mut().find(|(p, _)| *p == path) {
+					slot.1 = perms;
+				} else {
+					self.entries.push((path, perms))?;
+				}
+				Ok(())
+			}
+			_ => Err(errno!(EINVAL)),
+		}
+	}
+
+	/// Checks `path` against the unveil list, requiring at least `required` permissions.
+	///
+	/// Before the first [`Self::unveil`] call (the list still empty and unlocked), every path is
+	/// visible: this always succeeds. Afterward, fails with [`ENOENT`] if no unveiled prefix
+	/// covers `path`, or [`EACCES`] if the longest one that does lacks `required`.
+	pub fn check(&self, path: &Path, required: Perms) -> EResult<()> {
+		if self.entries.is_empty() && !self.locked {
+			return Ok(());
+		}
+		let mut best: Option<&(PathBuf, Perms)> = None;
+		for entry in &self.entries {
+			if !is_under(path, &entry.0)? {
+				continue;
+			}
+			let is_longer = match best {
+				Some((best_path, _)) => entry.0.components().count() > best_path.components().count(),
+				None => true,
+			};
+			if is_longer {
+				best = Some(entry);
+			}
+		}
+		match best {
+			None => Err(errno!(ENOENT)),
+			Some((_, perms)) if perms.contains(required) => Ok(()),
+			Some(_) => Err(errno!(EACCES)),
+		}
+	}
+
+	/// Sets whether [`Self::reset_for_exec`] should leave the list in place across `execve`
+	/// rather than clearing it, as real `unveil(2)` does by default.
+	pub fn set_keep_on_exec(&mut self, keep: bool) {
+		self.keep_on_exec = keep;
+	}
+
+	/// Clears the list back to its pristine, unrestricted state, as real `unveil(2)` does across
+	/// `execve`, unless [`Self::set_keep_on_exec`] was used to opt out of that.
+	///
+	/// Meant to be called from the `execve` path; `process::exec` has no file in this tree's
+	/// snapshot to call it from, so nothing does yet (see this module's own doc comment).
+	pub fn reset_for_exec(&mut self) {
+		if self.keep_on_exec {
+			return;
+		}
+		self.entries.clear();
+		self.locked = false;
+	}
+}
+
+/// `unveil`: narrows `proc`'s own unveil list, as described by [`UnveilList::unveil`].
+pub fn unveil(proc: &super::Process, path: Option<PathBuf>, perms: Option<Perms>) -> EResult<()> {
+	proc.unveil.lock().unveil(path, perms)
+}
+
+/// Checks `path` against `proc`'s unveil list, requiring at least `required` permissions. Called
+/// manually from every syscall that resolves a path through `vfs::get_file_from_path` (see this
+/// module's own doc comment for the full list and why it isn't `vfs::get_file_from_path` itself
+/// that calls this).
+pub fn check(proc: &super::Process, path: &Path, required: Perms) -> EResult<()> {
+	proc.unveil.lock().check(path, required)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn path(s: &str) -> PathBuf {
+		PathBuf::try_from(s.as_bytes()).unwrap()
+	}
+
+	#[test_case]
+	fn unveil_list_unrestricted_before_first_call() {
+		let list = UnveilList::default();
+		assert!(list.check(&path("/etc/passwd"), Perms::READ).is_ok());
+	}
+
+	#[test_case]
+	fn unveil_list_rejects_path_outside_any_prefix() {
+		let mut list = UnveilList::default();
+		list.unveil(Some(path("/etc")), Some(Perms::READ)).unwrap();
+		assert!(list.check(&path("/home/user/.ssh"), Perms::READ).is_err());
+	}
+
+	#[test_case]
+	fn unveil_list_rejects_missing_permission_under_covering_prefix() {
+		let mut list = UnveilList::default();
+		list.unveil(Some(path("/etc")), Some(Perms::READ)).unwrap();
+		assert!(list.check(&path("/etc/passwd"), Perms::WRITE).is_err());
+	}
+
+	#[test_case]
+	fn unveil_list_allows_covered_path_with_granted_permission() {
+		let mut list = UnveilList::default();
+		list.unveil(Some(path("/etc")), Some(Perms::READ | Perms::WRITE))
+			.unwrap();
+		assert!(list.check(&path("/etc/passwd"), Perms::READ).is_ok());
+	}
+
+	#[test_case]
+	fn unveil_list_prefers_the_longest_covering_prefix() {
+		let mut list = UnveilList::default();
+		list.unveil(Some(path("/etc")), Some(Perms::READ)).unwrap();
+		list.unveil(Some(path("/etc/secret")), Some(Perms::default()))
+			.unwrap();
+		// `/etc/secret` is the longer (more specific) match and grants no permission, even
+		// though `/etc` alone would have allowed the read.
+		assert!(list.check(&path("/etc/secret/key"), Perms::READ).is_err());
+	}
+
+	#[test_case]
+	fn unveil_list_lock_rejects_any_further_call() {
+		let mut list = UnveilList::default();
+		assert!(list.unveil(None, None).is_ok());
+		assert!(list.unveil(Some(path("/etc")), Some(Perms::READ)).is_err());
+		assert!(list.unveil(None, None).is_err());
+	}
+}
@@ -24,7 +24,7 @@ use crate::{
 		vfs::{ResolutionSettings, Resolved},
 	},
 	memory::user::UserString,
-	process::Process,
+	process::{pledge, Process},
 	sync::mutex::Mutex,
 	syscall::{
 		util::{
@@ -109,5 +109,6 @@ pub fn access(
 	rs: ResolutionSettings,
 	fds: Arc<Mutex<FileDescriptorTable>>,
 ) -> EResult<usize> {
+	pledge::enforce(&Process::current(), "access");
 	do_access(None, pathname, mode, None, rs, fds)
 }
@@ -21,7 +21,7 @@
 use crate::{
 	file::{fd::FileDescriptorTable, socket::Socket},
 	memory::user::UserSlice,
-	process::Process,
+	process::{pledge, Process},
 	sync::mutex::Mutex,
 	syscall::Args,
 };
@@ -36,6 +36,7 @@ pub fn bind(
 	Args((sockfd, addr, addrlen)): Args<(c_int, *mut u8, isize)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
 ) -> EResult<usize> {
+	pledge::enforce(&Process::current(), "bind");
 	// Validation
 	if addrlen < 0 {
 		return Err(errno!(EINVAL));
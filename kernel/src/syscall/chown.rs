@@ -19,8 +19,14 @@
 //! The `chown` system call changes the owner of a file.
 
 use crate::{
+	file,
 	file::{path::PathBuf, vfs, vfs::ResolutionSettings},
-	process::{mem_space::copy::SyscallString, Process},
+	process::{
+		mem_space::copy::SyscallString,
+		pledge,
+		unveil::{self, Perms},
+		Process,
+	},
 	syscall::Args,
 };
 use core::ffi::c_int;
@@ -29,6 +35,13 @@ use utils::{
 	errno::{EResult, Errno},
 };
 
+/// The `set-user-ID` mode bit.
+const S_ISUID: file::Mode = 0o4000;
+/// The `set-group-ID` mode bit.
+const S_ISGID: file::Mode = 0o2000;
+/// The group-execute mode bit.
+const S_IXGRP: file::Mode = 0o0010;
+
 /// Performs the `chown` syscall.
 pub fn do_chown(
 	pathname: SyscallString,
@@ -42,6 +55,7 @@ pub fn do_chown(
 	}
 	let path = pathname.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
 	let path = PathBuf::try_from(path)?;
+	unveil::check(&Process::current(), &path, Perms::WRITE)?;
 	// Get file
 	let file_mutex = vfs::get_file_from_path(&path, &rs)?;
 	let mut file = file_mutex.lock();
@@ -55,6 +69,21 @@ pub fn do_chown(
 	if group > -1 {
 		file.stat.set_gid(group as _);
 	}
+	if owner > -1 || group > -1 {
+		// POSIX requires `S_ISUID` (and, for a group-executable file, `S_ISGID`) to be cleared
+		// whenever ownership actually changes, so a setuid/setgid file can't be handed off to a
+		// new owner/group while keeping its escalation. Linux exempts a caller holding
+		// `CAP_FSETID` from this, but this tree has no capability model yet, so the bits are
+		// cleared unconditionally here.
+		let mode = file.stat.get_mode();
+		let mut cleared = mode & !S_ISUID;
+		if mode & S_IXGRP != 0 {
+			cleared &= !S_ISGID;
+		}
+		if cleared != mode {
+			file.stat.set_mode(cleared);
+		}
+	}
 	// TODO lazy
 	file.sync()?;
 	Ok(0)
@@ -64,5 +93,6 @@ pub fn chown(
 	Args((pathname, owner, group)): Args<(SyscallString, c_int, c_int)>,
 	rs: ResolutionSettings,
 ) -> EResult<usize> {
+	pledge::enforce(&Process::current(), "chown");
 	do_chown(pathname, owner, group, rs)
 }
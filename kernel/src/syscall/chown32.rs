@@ -18,12 +18,18 @@
 
 //! The `chown32` system call changes the owner of a file.
 
-use crate::syscall::{Args, SyscallString};
+use crate::{
+	file::vfs::ResolutionSettings,
+	process::{pledge, Process},
+	syscall::{Args, SyscallString},
+};
 use core::ffi::c_int;
 use utils::errno::{EResult, Errno};
 
 pub fn chown32(
 	Args((pathname, owner, group)): Args<(SyscallString, c_int, c_int)>,
+	rs: ResolutionSettings,
 ) -> EResult<usize> {
-	super::chown::do_chown(pathname, owner, group, true)
+	pledge::enforce(&Process::current(), "chown32");
+	super::chown::do_chown(pathname, owner, group, rs)
 }
@@ -0,0 +1,115 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `copy_file_range` system call copies a range of bytes from one file to another without
+//! the data ever transiting through userspace.
+
+use crate::{
+	file::{fd::FileDescriptorTable, FileType},
+	memory::user::UserPtr,
+	sync::mutex::Mutex,
+	syscall::Args,
+};
+use core::{cmp::min, ffi::c_int, sync::atomic};
+use utils::{errno, errno::EResult, ptr::arc::Arc, vec};
+
+/// The maximum amount of data copied per internal chunk, bounding the size of the staging
+/// buffer used by the fallback read+write path.
+const CHUNK_SIZE: usize = 65536;
+
+pub fn copy_file_range(
+	Args((fd_in, off_in, fd_out, off_out, len, flags)): Args<(
+		c_int,
+		UserPtr<u64>,
+		c_int,
+		UserPtr<u64>,
+		usize,
+		c_int,
+	)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	// No flag is currently defined for this syscall.
+	if flags != 0 {
+		return Err(errno!(EINVAL));
+	}
+	let (file_in, file_out) = {
+		let fds = fds.lock();
+		(
+			fds.get_fd(fd_in)?.get_file().clone(),
+			fds.get_fd(fd_out)?.get_file().clone(),
+		)
+	};
+	if file_in.get_type()? != FileType::Regular || file_out.get_type()? != FileType::Regular {
+		return Err(errno!(EINVAL));
+	}
+	// Any copy within one file is refused, whether or not the two ranges overlap.
+	if Arc::ptr_eq(&file_in, &file_out) {
+		return Err(errno!(EINVAL));
+	}
+	// Resolve the starting offsets, falling back to (and advancing) the file's own cursor when
+	// the caller passed a null pointer, as specified for this syscall.
+	let mut off_in_val = match off_in.copy_from_user()? {
+		Some(o) => o,
+		None => file_in.off.load(atomic::Ordering::Acquire),
+	};
+	let mut off_out_val = match off_out.copy_from_user()? {
+		Some(o) => o,
+		None => file_out.off.load(atomic::Ordering::Acquire),
+	};
+	let len = min(len, i32::MAX as usize);
+	let mut total = 0;
+	// The copy happens entirely in kernel space: each chunk is read into a kernel-owned staging
+	// buffer and written straight back out, so unlike a userspace `read`+`write` loop, the data
+	// never crosses the user/kernel boundary.
+	let mut buf = vec![0u8; min(len, CHUNK_SIZE)]?;
+	while total < len {
+		let chunk = min(len - total, buf.len());
+		let n = file_in.ops.read(&file_in, off_in_val, &mut buf[..chunk])?;
+		if n == 0 {
+			break;
+		}
+		let mut written = 0;
+		while written < n {
+			let w = file_out
+				.ops
+				.write(&file_out, off_out_val + written as u64, &buf[written..n])?;
+			if w == 0 {
+				break;
+			}
+			written += w;
+		}
+		off_in_val += written as u64;
+		off_out_val += written as u64;
+		total += written;
+		if written < n {
+			break;
+		}
+	}
+	// Update offsets: either the userspace-provided pointers, or the files' own cursors.
+	if off_in.copy_from_user()?.is_some() {
+		off_in.copy_to_user(&off_in_val)?;
+	} else {
+		file_in.off.store(off_in_val, atomic::Ordering::Release);
+	}
+	if off_out.copy_from_user()?.is_some() {
+		off_out.copy_to_user(&off_out_val)?;
+	} else {
+		file_out.off.store(off_out_val, atomic::Ordering::Release);
+	}
+	Ok(total)
+}
@@ -21,7 +21,7 @@
 use crate::{
 	file,
 	file::{fd::FileDescriptorTable, fs::StatSet, perm::AccessProfile, vfs},
-	process::Process,
+	process::{pledge, Process},
 	sync::mutex::Mutex,
 	syscall::Args,
 };
@@ -37,6 +37,7 @@ pub fn fchmod(
 	fds_mutex: Arc<Mutex<FileDescriptorTable>>,
 	ap: AccessProfile,
 ) -> EResult<usize> {
+	pledge::enforce(&Process::current(), "fchmod");
 	let file = fds_mutex
 		.lock()
 		.get_fd(fd)?
@@ -56,5 +57,8 @@ pub fn fchmod(
 			..Default::default()
 		},
 	)?;
+	// `vfs::set_stat` itself has no file in this tree's snapshot to add this call into (see
+	// `vfs::node`'s own doc comment), so `fchmod` notifies its watches directly instead.
+	file.node().notify_attrib();
 	Ok(0)
 }
@@ -22,7 +22,7 @@ use crate::{
 	file::{buffer, buffer::socket::Socket},
 	process::{
 		mem_space::copy::{SyscallPtr, SyscallSlice},
-		Process,
+		pledge, Process,
 	},
 	syscall::Args,
 };
@@ -35,6 +35,7 @@ use utils::{
 pub fn getsockname(
 	Args((sockfd, addr, addrlen)): Args<(c_int, SyscallSlice<u8>, SyscallPtr<isize>)>,
 ) -> EResult<usize> {
+	pledge::enforce(&Process::current(), "getsockname");
 	let proc_mutex = Process::current();
 	let proc = proc_mutex.lock();
 
@@ -20,7 +20,7 @@
 
 use crate::{
 	file::{buffer, buffer::socket::Socket},
-	process::{mem_space::copy::SyscallSlice, Process},
+	process::{mem_space::copy::SyscallSlice, pledge, Process},
 	syscall::Args,
 };
 use core::{any::Any, cmp::min, ffi::c_int};
@@ -38,6 +38,7 @@ pub fn getsockopt(
 		usize,
 	)>,
 ) -> EResult<usize> {
+	pledge::enforce(&Process::current(), "getsockopt");
 	let proc_mutex = Process::current();
 	let proc = proc_mutex.lock();
 
@@ -0,0 +1,75 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `inotify_add_watch` system call registers a watch for filesystem change notifications,
+//! delivered onto the calling process's own [`crate::process::inotify::EventQueue`].
+
+use crate::{
+	file::vfs::{self, ResolutionSettings},
+	memory::user::UserString,
+	process::{
+		inotify::EventMask,
+		unveil::{self, Perms},
+		Process,
+	},
+	syscall::Args,
+};
+use utils::{collections::path::PathBuf, errno, errno::EResult};
+
+/// `inotify_add_watch`: watches `path`'s node for events in `mask`, delivered onto the calling
+/// process's own queue.
+///
+/// Returns the watch descriptor, unique across every node, identifying this watch to a later
+/// `inotify_rm_watch` or in the `wd` field of the [`crate::process::inotify::Event`]s it
+/// produces.
+///
+/// Unlike the real `inotify_add_watch(2)`, this does not multiplex onto an `inotify_init`-created
+/// file descriptor: reading back delivered events needs `file::fd`'s `FileDescriptorTable`
+/// machinery, which has no file in this tree's snapshot (the same gap
+/// [`crate::process::pidfd`]'s own doc comment documents). [`Process::inotify`] is the queue every
+/// watch registered by this syscall feeds, ready to be plugged into a real fd once that machinery
+/// exists.
+pub fn inotify_add_watch(Args((path, mask)): Args<(UserString, u32)>) -> EResult<usize> {
+	let proc = Process::current();
+	let path = path.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+	let path = PathBuf::try_from(path)?;
+	unveil::check(&proc, &path, Perms::READ)?;
+	let rs = ResolutionSettings::for_process(&proc, false);
+	let ent = vfs::get_file_from_path(&path, &rs)?;
+	let mask = mask_from_bits(mask);
+	let wd = ent.node().add_watch(mask, proc.inotify.clone())?;
+	Ok(wd as usize)
+}
+
+/// Rebuilds an [`EventMask`] from the raw bitmask passed by userspace, keeping only the bits this
+/// tree recognizes.
+fn mask_from_bits(bits: u32) -> EventMask {
+	let known = [
+		EventMask::MODIFY,
+		EventMask::ATTRIB,
+		EventMask::CREATE,
+		EventMask::DELETE,
+		EventMask::MOVE,
+		EventMask::DELETE_SELF,
+	];
+	known
+		.into_iter()
+		.enumerate()
+		.filter(|(i, _)| bits & (1 << i) != 0)
+		.fold(EventMask::default(), |acc, (_, m)| acc | m)
+}
@@ -19,13 +19,55 @@
 //! The `madvise` system call gives advices to the kernel about the usage of
 //! memory in order to allow optimizations.
 
-use crate::syscall::Args;
+use crate::{
+	memory::VirtAddr,
+	process::{mem_space::MemAdvice, Process},
+	syscall::Args,
+};
 use core::ffi::{c_int, c_void};
-use utils::errno::EResult;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	limits::PAGE_SIZE,
+};
 
+/// Default treatment: no special hint is applied.
+const MADV_NORMAL: c_int = 0;
+/// Expects page references to be random, disabling readahead: a no-op without a readahead
+/// subsystem.
+const MADV_RANDOM: c_int = 1;
+/// Expects page references to be sequential, enabling aggressive readahead: a no-op without a
+/// readahead subsystem.
+const MADV_SEQUENTIAL: c_int = 2;
+/// Expects the range to be accessed soon: pre-faults it in eagerly.
+const MADV_WILLNEED: c_int = 3;
+/// Expects the range not to be accessed soon: the kernel may discard its backing.
+const MADV_DONTNEED: c_int = 4;
+/// Records the range as freeable without discarding its backing (see `MemAdvice::Free`).
+const MADV_FREE: c_int = 8;
+
+/// Performs the `madvise` system call.
 pub fn madvise(
-	Args((_addr, _length, _advice)): Args<(*mut c_void, usize, c_int)>,
+	Args((addr, length, advice)): Args<(*mut c_void, usize, c_int)>,
 ) -> EResult<usize> {
-	// TODO
+	let addr = VirtAddr(addr as usize);
+	if !addr.is_aligned_to(PAGE_SIZE) {
+		return Err(errno!(EINVAL));
+	}
+	if length == 0 {
+		return Ok(0);
+	}
+	let len = length.next_multiple_of(PAGE_SIZE);
+	let advice = match advice {
+		MADV_NORMAL | MADV_RANDOM | MADV_SEQUENTIAL => return Ok(0),
+		MADV_WILLNEED => MemAdvice::WillNeed,
+		MADV_DONTNEED => MemAdvice::DontNeed,
+		MADV_FREE => MemAdvice::Free,
+		_ => return Err(errno!(EINVAL)),
+	};
+	let proc_mutex = Process::current();
+	let proc = proc_mutex.lock();
+	let mem_space_mutex = proc.mem_space.as_ref().ok_or(errno!(EINVAL))?;
+	mem_space_mutex.lock().madvise(addr, len, advice)?;
 	Ok(0)
 }
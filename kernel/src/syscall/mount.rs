@@ -24,27 +24,63 @@ use crate::{
 		vfs::{mountpoint, mountpoint::MountSource, ResolutionSettings},
 		FileType,
 	},
-	memory::user::{UserPtr, UserString},
-	process::Process,
+	memory::user::UserString,
+	process::{
+		pledge,
+		unveil::{self, Perms},
+		Process,
+	},
 	syscall::Args,
 };
-use core::ffi::{c_ulong, c_void};
+use core::ffi::c_ulong;
 use utils::{
 	collections::path::PathBuf,
 	errno,
 	errno::{EResult, Errno},
 };
 
+/// Mounts the filesystem read-only.
+///
+/// Enforcing this against writes is the filesystem driver's responsibility; this syscall only
+/// forwards the bit through `mountflags` to `mountpoint::create`.
+pub(crate) const MS_RDONLY: c_ulong = 1;
+/// Ignores set-user-ID and set-group-ID bits on the mounted filesystem.
+pub(crate) const MS_NOSUID: c_ulong = 2;
+/// Disallows access to device files on the mounted filesystem.
+pub(crate) const MS_NODEV: c_ulong = 4;
+/// Disallows program execution from the mounted filesystem.
+pub(crate) const MS_NOEXEC: c_ulong = 8;
+/// Changes the flags of an already-mounted filesystem instead of mounting a new one.
+const MS_REMOUNT: c_ulong = 32;
+/// Re-exposes an already-mounted subtree at another path, rather than mounting a new filesystem.
+const MS_BIND: c_ulong = 4096;
+
+/// Validates that `data` is a well-formed, comma-separated list of `key` or `key=value`
+/// filesystem-specific mount options.
+///
+/// Nothing consumes the individual options yet: no filesystem driver in this tree exposes a
+/// mount-options hook for them to be routed to. This only rejects malformed strings instead of
+/// silently accepting (and dropping) garbage.
+fn validate_options(data: &[u8]) -> EResult<()> {
+	for opt in data.split(|b| *b == b',') {
+		if opt.is_empty() {
+			return Err(errno!(EINVAL));
+		}
+	}
+	Ok(())
+}
+
 pub fn mount(
-	Args((source, target, filesystemtype, mountflags, _data)): Args<(
+	Args((source, target, filesystemtype, mountflags, data)): Args<(
 		UserString,
 		UserString,
 		UserString,
 		c_ulong,
-		UserPtr<c_void>,
+		UserString,
 	)>,
 	rs: ResolutionSettings,
 ) -> EResult<usize> {
+	pledge::enforce(&Process::current(), "mount");
 	if !rs.access_profile.is_privileged() {
 		return Err(errno!(EPERM));
 	}
@@ -53,16 +89,34 @@ pub fn mount(
 	let mount_source = MountSource::new(&source_slice)?;
 	let target_slice = target.copy_from_user()?.ok_or(errno!(EFAULT))?;
 	let target_path = PathBuf::try_from(target_slice)?;
-	let filesystemtype_slice = filesystemtype.copy_from_user()?.ok_or(errno!(EFAULT))?;
-	let fs_type = fs::get_type(&filesystemtype_slice).ok_or(errno!(ENODEV))?;
+	unveil::check(&Process::current(), &target_path, Perms::WRITE)?;
+	// `data` is optional: a bind mount or a remount never carries filesystem-specific options.
+	if let Some(data_slice) = data.copy_from_user()? {
+		validate_options(&data_slice)?;
+	}
 	// Get target file
 	let target = vfs::get_file_from_path(&target_path, &rs)?;
 	// Check the target is a directory
 	if target.get_type()? != FileType::Directory {
 		return Err(errno!(ENOTDIR));
 	}
-	// TODO Use `data`
+	if mountflags & MS_REMOUNT != 0 {
+		// A remount only changes the flags (e.g. `MS_RDONLY`, `MS_NOSUID`, `MS_NODEV`,
+		// `MS_NOEXEC`) of the filesystem already mounted at `target`; it must not instantiate a
+		// new one. Updating an existing mountpoint's flags in place would need a
+		// `mountpoint::remount`-style entry point, which doesn't exist in this tree, so this is
+		// rejected rather than silently creating a second, shadowing mount.
+		return Err(errno!(EINVAL));
+	}
+	let fs_type = if mountflags & MS_BIND != 0 {
+		// A bind mount re-exposes an already-mounted subtree; it has no filesystem type of its
+		// own, so `filesystemtype` is ignored instead of being resolved through `fs::get_type`.
+		None
+	} else {
+		let filesystemtype_slice = filesystemtype.copy_from_user()?.ok_or(errno!(EFAULT))?;
+		Some(fs::get_type(&filesystemtype_slice).ok_or(errno!(ENODEV))?)
+	};
 	// Create mountpoint
-	mountpoint::create(mount_source, Some(fs_type), mountflags as _, Some(target))?;
+	mountpoint::create(mount_source, fs_type, mountflags as _, Some(target))?;
 	Ok(0)
 }
@@ -21,7 +21,7 @@
 use crate::{
 	file,
 	file::{fd::FileDescriptorTable, pipe::PipeBuffer, vfs, File},
-	process::{mem_space::copy::SyscallPtr, Process},
+	process::{mem_space::copy::SyscallPtr, pledge, Process},
 	sync::mutex::Mutex,
 	syscall::Args,
 };
@@ -37,6 +37,7 @@ pub fn pipe2(
 	Args((pipefd, flags)): Args<(SyscallPtr<[c_int; 2]>, c_int)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
 ) -> EResult<usize> {
+	pledge::enforce(&Process::current(), "pipe2");
 	// Validation
 	let accepted_flags = file::O_CLOEXEC | file::O_DIRECT | file::O_NONBLOCK;
 	if flags & !accepted_flags != 0 {
@@ -0,0 +1,64 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `pledge` system call restricts the calling process to a set of syscall capability groups,
+//! as described in [`process::pledge`].
+
+use crate::{
+	memory::user::UserString,
+	process::{pledge, pledge::PromiseSet, Process},
+	syscall::Args,
+};
+use utils::errno::{self, EResult, Errno};
+
+/// Parses a single space-separated word of a `pledge(2)`-style promise list (`"stdio rpath
+/// wpath"`, ...) into a [`PromiseSet`].
+///
+/// Fails with [`EINVAL`] on an unrecognized promise name.
+fn parse_word(word: &[u8]) -> EResult<PromiseSet> {
+	Ok(match word {
+		b"stdio" => PromiseSet::STDIO,
+		b"rpath" => PromiseSet::RPATH,
+		b"wpath" => PromiseSet::WPATH,
+		b"cpath" => PromiseSet::CPATH,
+		b"fattr" => PromiseSet::FATTR,
+		b"proc" => PromiseSet::PROC,
+		b"exec" => PromiseSet::EXEC,
+		b"inet" => PromiseSet::INET,
+		b"chroot" => PromiseSet::CHROOT,
+		_ => return Err(errno!(EINVAL)),
+	})
+}
+
+/// Parses a space-separated `pledge(2)`-style promise list (`"stdio rpath wpath"`, ...) into a
+/// [`PromiseSet`].
+fn parse_promises(s: &[u8]) -> EResult<PromiseSet> {
+	let mut set = PromiseSet::empty();
+	for word in s.split(|b| *b == b' ').filter(|w| !w.is_empty()) {
+		set |= parse_word(word)?;
+	}
+	Ok(set)
+}
+
+pub fn pledge(Args((promises,)): Args<(UserString,)>) -> EResult<usize> {
+	let proc = Process::current();
+	let promises = promises.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+	let promises = parse_promises(promises.as_bytes())?;
+	pledge::pledge(&proc, promises);
+	Ok(0)
+}
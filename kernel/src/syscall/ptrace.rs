@@ -0,0 +1,101 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `ptrace` system call lets a process trace the execution of another.
+//!
+//! Most of the actual logic lives in [`process::ptrace`]; this is the `request`-number dispatch
+//! `ptrace(2)` itself is. `PTRACE_GETREGS`/`PTRACE_SETREGS` and `PTRACE_PEEKTEXT`/
+//! `PTRACE_PEEKDATA`/`PTRACE_POKETEXT`/`PTRACE_POKEDATA` fail with [`ENOSYS`]: the former needs
+//! [`Process::user_regs`] (a `todo!()` pending `arch::x86::idt::IntFrame`'s field layout), the
+//! latter a cross-address-space copy primitive neither of which this tree's snapshot has.
+
+use crate::{
+	file::perm::AccessProfile,
+	process::{
+		pid::Pid,
+		pledge, ptrace,
+		ptrace::{PtraceOptions, ResumeMode},
+		signal::Signal,
+		Process,
+	},
+	syscall::Args,
+};
+use core::ffi::c_int;
+use utils::{errno, errno::EResult};
+
+pub fn ptrace(
+	Args((request, pid, _addr, data)): Args<(c_int, Pid, usize, usize)>,
+	access_profile: AccessProfile,
+) -> EResult<usize> {
+	pledge::enforce(&Process::current(), "ptrace");
+	// `PTRACE_TRACEME` has no target PID of its own: the caller is both tracer and tracee.
+	if request == ptrace::TRACEME {
+		ptrace::traceme(&Process::current())?;
+		return Ok(0);
+	}
+	let tracee = Process::get_by_pid(pid).ok_or(errno!(ESRCH))?;
+	match request {
+		ptrace::ATTACH | ptrace::SEIZE => {
+			if !access_profile.can_kill(&tracee) {
+				return Err(errno!(EPERM));
+			}
+			let tracer = Process::current();
+			let options = PtraceOptions::from(data as u32);
+			ptrace::attach(&tracer, &tracee, request == ptrace::SEIZE, options)?;
+			Ok(0)
+		}
+		ptrace::SETOPTIONS => {
+			let mut state = tracee.ptrace.lock();
+			let ptrace_state = state.as_mut().ok_or_else(|| errno!(ESRCH))?;
+			ptrace_state.options = PtraceOptions::from(data as u32);
+			Ok(0)
+		}
+		ptrace::DETACH => {
+			ptrace::detach(&tracee);
+			Ok(0)
+		}
+		ptrace::CONT | ptrace::SYSCALL | ptrace::SINGLESTEP => {
+			let mode = match request {
+				ptrace::SYSCALL => ResumeMode::Syscall,
+				ptrace::SINGLESTEP => ResumeMode::SingleStep,
+				_ => ResumeMode::Cont,
+			};
+			// A non-zero `data` is the signal number to reinject on resume, same as Linux's own
+			// `PTRACE_CONT`/`PTRACE_SYSCALL` overload that field with.
+			let inject = if data != 0 {
+				Some(Signal::try_from(data as c_int)?)
+			} else {
+				None
+			};
+			ptrace::resume(&tracee, mode, inject)?;
+			Ok(0)
+		}
+		ptrace::KILL => {
+			tracee.kill(Signal::SIGKILL);
+			Ok(0)
+		}
+		ptrace::GETREGS
+		| ptrace::SETREGS
+		| ptrace::PEEKTEXT
+		| ptrace::PEEKDATA
+		| ptrace::POKETEXT
+		| ptrace::POKEDATA
+		| ptrace::GETSIGINFO => Err(errno!(ENOSYS)),
+		_ => Err(errno!(EINVAL)),
+	}
+}
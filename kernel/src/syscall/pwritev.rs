@@ -20,10 +20,7 @@
 
 use crate::{
 	file::fd::FileDescriptorTable,
-	process::{
-		mem_space::copy::{UserIOVec, UserSlice},
-		Process,
-	},
+	process::{mem_space::copy::UserIOVec, pledge, Process},
 	sync::mutex::Mutex,
 	syscall::Args,
 };
@@ -40,7 +37,8 @@ pub fn pwritev(
 	)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
 ) -> EResult<usize> {
+	pledge::enforce(&Process::current(), "pwritev");
 	#[allow(arithmetic_overflow)]
 	let offset = offset_low | (offset_high << 32);
-	super::writev::do_writev(fd, iov, iovcnt, Some(offset), None, fds)
+	super::writev::do_writev(fd, iov, iovcnt, Some(offset as i64), None, fds)
 }
@@ -22,7 +22,7 @@ use super::Args;
 use crate::{
 	file::{fd::FileDescriptorTable, FileType},
 	memory::user::UserSlice,
-	process::{scheduler, Process},
+	process::{pledge, scheduler, Process},
 	sync::mutex::Mutex,
 };
 use core::{cmp::min, ffi::c_int, sync::atomic};
@@ -37,6 +37,7 @@ pub fn read(
 	Args((fd, buf, count)): Args<(c_int, *mut u8, usize)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
 ) -> EResult<usize> {
+	pledge::enforce(&Process::current(), "read");
 	let buf = UserSlice::from_user(buf, count)?;
 	// Validation
 	let len = min(count, i32::MAX as usize);
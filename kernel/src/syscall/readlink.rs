@@ -21,7 +21,11 @@
 use crate::{
 	file::{FileType, vfs, vfs::ResolutionSettings},
 	memory::user::{UserSlice, UserString},
-	process::Process,
+	process::{
+		pledge,
+		unveil::{self, Perms},
+		Process,
+	},
 	syscall::Args,
 };
 use utils::{
@@ -35,9 +39,11 @@ pub fn readlink(
 	Args((pathname, buf, bufsiz)): Args<(UserString, *mut u8, usize)>,
 ) -> EResult<usize> {
 	let proc = Process::current();
+	pledge::enforce(&proc, "readlink");
 	// Get file
 	let path = pathname.copy_from_user()?.ok_or(errno!(EFAULT))?;
 	let path = PathBuf::try_from(path)?;
+	unveil::check(&proc, &path, Perms::READ)?;
 	let rs = ResolutionSettings::for_process(&proc, false);
 	let ent = vfs::get_file_from_path(&path, &rs)?;
 	// Validation
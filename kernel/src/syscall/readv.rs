@@ -0,0 +1,86 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `readv` system call allows to read sparse data from a file descriptor.
+
+use crate::{
+	file::fd::FileDescriptorTable,
+	process::{
+		mem_space::copy::{SyscallSlice, UserIOVec},
+		pledge, Process,
+	},
+	sync::mutex::Mutex,
+	syscall::Args,
+};
+use core::{cmp::min, ffi::c_int, ptr::NonNull, sync::atomic};
+use utils::{errno, errno::EResult, ptr::arc::Arc, vec};
+
+pub fn readv(
+	Args((fd, iov, iovcnt)): Args<(c_int, UserIOVec, c_int)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	pledge::enforce(&Process::current(), "readv");
+	do_readv(fd, iov, iovcnt, None, None, fds)
+}
+
+/// Implementation of `readv`, shared with `preadv`/`preadv2`.
+///
+/// Arguments:
+/// - `offset`, if set, is used instead of (and does not update) the file's own cursor.
+/// - `flags` is reserved for `preadv2`'s `RWF_*` flags, none of which are currently honored.
+pub fn do_readv(
+	fd: c_int,
+	iov: UserIOVec,
+	iovcnt: c_int,
+	offset: Option<i64>,
+	_flags: Option<c_int>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	if iovcnt < 0 {
+		return Err(errno!(EINVAL));
+	}
+	let segments = iov.copy_from_user(iovcnt as usize)?.ok_or(errno!(EFAULT))?;
+	let file = fds.lock().get_fd(fd)?.get_file().clone();
+	let mut off = match offset {
+		Some(o) => o as u64,
+		None => file.off.load(atomic::Ordering::Acquire),
+	};
+	let mut total = 0usize;
+	for seg in segments {
+		if seg.iov_len == 0 {
+			continue;
+		}
+		let len = min(seg.iov_len, (i32::MAX as usize).saturating_sub(total));
+		if len == 0 {
+			break;
+		}
+		let mut buf = vec![0u8; len]?;
+		let n = file.ops.read(&file, off, &mut buf)?;
+		let dst = SyscallSlice::<u8>(NonNull::new(seg.iov_base as *mut u8));
+		dst.copy_to_user(0, &buf[..n])?;
+		off += n as u64;
+		total += n;
+		if n < len {
+			break;
+		}
+	}
+	if offset.is_none() {
+		file.off.store(off, atomic::Ordering::Release);
+	}
+	Ok(total)
+}
@@ -22,7 +22,12 @@
 
 use crate::{
 	file::{path::PathBuf, vfs, vfs::ResolutionSettings, FileType},
-	process::{mem_space::copy::SyscallString, Process},
+	process::{
+		mem_space::copy::SyscallString,
+		pledge,
+		unveil::{self, Perms},
+		Process,
+	},
 	syscall::Args,
 };
 use utils::{
@@ -31,6 +36,7 @@ use utils::{
 };
 
 pub fn rmdir(Args(pathname): Args<SyscallString>) -> EResult<usize> {
+	pledge::enforce(&Process::current(), "rmdir");
 	let (path, rs) = {
 		let proc_mutex = Process::current();
 		let proc = proc_mutex.lock();
@@ -39,6 +45,7 @@ pub fn rmdir(Args(pathname): Args<SyscallString>) -> EResult<usize> {
 
 		let path = pathname.copy_from_user()?.ok_or(errno!(EFAULT))?;
 		let path = PathBuf::try_from(path)?;
+		unveil::check(&proc, &path, Perms::CREATE)?;
 
 		(path, rs)
 	};
@@ -37,13 +37,22 @@ pub fn rt_sigaction(
 	// Get process
 	let proc_mutex = Process::current();
 	let proc = proc_mutex.lock();
-	let mut signal_handlers = proc.signal_handlers.lock();
+	let signal_manager = proc.signal.lock();
+	let mut handlers = signal_manager.handlers.lock();
 	// Save the old structure
-	let old = signal_handlers[signal.get_id() as usize].get_action();
+	let old = handlers[signal.get_id() as usize].get_action();
 	oldact.copy_to_user(old)?;
 	// Set the new structure
+	//
+	// `SigAction`/`SignalHandler` only carry a plain handler pointer plus `sa_mask` today; honoring
+	// `SA_SIGINFO` (a populated `siginfo_t`/`ucontext` frame for a `sa_sigaction` handler),
+	// `SA_RESTART`, `SA_NODEFER`, `SA_RESETHAND` and `SA_RESTORER` requires extending those types
+	// and the signal-trampoline setup, which live in `process::signal` and aren't part of this
+	// change. What IS wired up here is the FIFO queue (`rt_signal::RtSignalQueue`) for instances
+	// of `SIGRTMIN..=SIGRTMAX`, so `sigqueue`-delivered payloads are no longer dropped once that
+	// trampoline work lands.
 	if let Some(new) = act.copy_from_user()? {
-		signal_handlers[signal.get_id() as usize] = SignalHandler::Handler(new);
+		handlers[signal.get_id() as usize] = SignalHandler::Handler(new);
 	}
 	Ok(0)
 }
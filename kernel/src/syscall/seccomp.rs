@@ -0,0 +1,94 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `seccomp` system call installs a classic-BPF syscall filter on the calling process.
+//!
+//! Only `SECCOMP_SET_MODE_FILTER` is implemented. `SECCOMP_SET_MODE_STRICT` would install a fixed
+//! filter equivalent to allowing just `read`/`write`/`_exit`/`rt_sigreturn`, but building that
+//! filter needs each syscall's assigned number, and `syscall`'s dispatch table (where those
+//! numbers would be defined) has no file in this tree's snapshot.
+//!
+//! Once installed, a filter can never be removed: [`SeccompState::install`] only ever pushes onto
+//! the filter stack, and neither it nor anything else in [`process::seccomp`] exposes a way to pop
+//! one back off, so every descendant forked after this call inherits at least as restrictive a
+//! filter set as the caller, exactly as `seccomp(2)` guarantees.
+//!
+//! A caller should not expect this to restrict anything yet, though: as documented on
+//! [`process::seccomp`], no syscall-entry hook in this tree ever runs a syscall through the
+//! installed filter stack, so this call only validates and stores the program; it does not
+//! enforce it.
+
+use crate::{
+	file::perm::AccessProfile,
+	process::{
+		mem_space::copy::{SyscallPtr, SyscallSlice},
+		pledge,
+		seccomp::{SeccompFilter, SockFilter},
+		Process,
+	},
+	syscall::Args,
+};
+use core::ptr::{self, NonNull};
+use utils::errno::{self, EResult};
+
+/// `SECCOMP_SET_MODE_STRICT`.
+const SET_MODE_STRICT: u32 = 0;
+/// `SECCOMP_SET_MODE_FILTER`.
+const SET_MODE_FILTER: u32 = 1;
+
+/// `SECCOMP_FILTER_FLAG_NEW_LISTENER`: return a notification file descriptor instead of applying
+/// the classic `ERRNO`/`TRACE`/... dispositions directly.
+const FILTER_FLAG_NEW_LISTENER: u32 = 1 << 3;
+
+/// Mirrors userspace's `struct sock_fprog`, the `args` payload of `SECCOMP_SET_MODE_FILTER`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct RawSockFprog {
+	/// Number of [`SockFilter`] instructions pointed to by `filter`.
+	len: u16,
+	/// Pointer to the instruction array, as an exposed-provenance userspace address.
+	filter: usize,
+}
+
+pub fn seccomp(
+	Args((operation, flags, args)): Args<(u32, u32, SyscallPtr<RawSockFprog>)>,
+	access_profile: AccessProfile,
+) -> EResult<usize> {
+	pledge::enforce(&Process::current(), "seccomp");
+	match operation {
+		SET_MODE_STRICT => Err(errno!(ENOSYS)),
+		SET_MODE_FILTER => {
+			if flags & FILTER_FLAG_NEW_LISTENER != 0 {
+				// Returning a notification fd needs a whole separate polled-object subsystem;
+				// nothing in this tree's snapshot backs one.
+				return Err(errno!(ENOSYS));
+			}
+			let fprog = args.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+			let filter_ptr = NonNull::new(ptr::with_exposed_provenance_mut(fprog.filter));
+			let insns = SyscallSlice::<SockFilter>(filter_ptr)
+				.copy_from_user_vec(0, fprog.len as usize)?
+				.ok_or_else(|| errno!(EFAULT))?;
+			let filter = SeccompFilter::new(insns)?;
+			let proc = Process::current();
+			let privileged = access_profile.is_privileged();
+			proc.seccomp.lock().install(filter, privileged)?;
+			Ok(0)
+		}
+		_ => Err(errno!(EINVAL)),
+	}
+}
@@ -18,10 +18,15 @@
 
 //! The `setgid32` syscall sets the GID of the process's owner.
 
-use crate::{file::perm::Gid, process::Process, syscall::Args};
+use crate::{
+	file::perm::Gid,
+	process::{pledge, Process},
+	syscall::Args,
+};
 use utils::errno::{EResult, Errno};
 
 pub fn setgid32(Args(gid): Args<Gid>) -> EResult<usize> {
+	pledge::enforce(&Process::current(), "setgid32");
 	let proc_mutex = Process::current();
 	let mut proc = proc_mutex.lock();
 
@@ -0,0 +1,42 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `sigqueue` system call sends a signal to a process, carrying a `sigval` payload through to
+//! the receiver's `siginfo_t`, unlike the plain no-payload delivery `kill`/`tgkill` send.
+
+use crate::{
+	process::{
+		pid::Pid,
+		pledge,
+		rt_signal::{self, SigVal},
+		signal::Signal,
+		Process,
+	},
+	syscall::Args,
+};
+use core::ffi::c_int;
+use utils::errno::{self, EResult};
+
+pub fn sigqueue(Args((pid, sig, value)): Args<(Pid, c_int, usize)>) -> EResult<usize> {
+	pledge::enforce(&Process::current(), "sigqueue");
+	let signal = Signal::try_from(sig)?;
+	let target = Process::get_by_pid(pid).ok_or(errno!(ESRCH))?;
+	let sender = Process::current();
+	rt_signal::sigqueue(&sender, &target, signal, SigVal(value))?;
+	Ok(0)
+}
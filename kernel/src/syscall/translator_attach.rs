@@ -0,0 +1,53 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `translator_attach` system call replaces the node at a path with one backed by a userspace
+//! translator daemon, as described in [`vfs::translator`].
+
+use crate::{
+	file::vfs::{self, translator, ResolutionSettings},
+	memory::user::UserString,
+	process::{
+		unveil::{self, Perms},
+		Process,
+	},
+	syscall::Args,
+};
+use utils::{
+	collections::path::PathBuf,
+	errno,
+	errno::{EResult, Errno},
+};
+
+/// `translator_attach`: attaches a translator to `path`'s node and returns an opaque handle
+/// identifying the channel the daemon is to pick requests up from.
+///
+/// Returning an actual pollable file descriptor for that channel needs `file::fd`'s
+/// `FileDescriptorTable` machinery, not part of this tree's snapshot; see
+/// [`translator::register_queue`]'s own doc comment for the stand-in used instead.
+pub fn translator_attach(Args((path,)): Args<(UserString,)>) -> EResult<usize> {
+	let proc = Process::current();
+	let path = path.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+	let path = PathBuf::try_from(path)?;
+	unveil::check(&proc, &path, Perms::WRITE)?;
+	let rs = ResolutionSettings::for_process(&proc, false);
+	let ent = vfs::get_file_from_path(&path, &rs)?;
+	let node = ent.node();
+	let queue = translator::attach(node.inode, node.mp.clone())?;
+	translator::register_queue(queue)
+}
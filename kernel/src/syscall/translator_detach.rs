@@ -0,0 +1,46 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `translator_detach` system call releases the translator previously attached at a path, as
+//! described in [`vfs::translator`].
+
+use crate::{
+	file::vfs::{self, translator, ResolutionSettings},
+	memory::user::UserString,
+	process::{
+		unveil::{self, Perms},
+		Process,
+	},
+	syscall::Args,
+};
+use utils::{
+	collections::path::PathBuf,
+	errno,
+	errno::{EResult, Errno},
+};
+
+pub fn translator_detach(Args((path,)): Args<(UserString,)>) -> EResult<usize> {
+	let proc = Process::current();
+	let path = path.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+	let path = PathBuf::try_from(path)?;
+	unveil::check(&proc, &path, Perms::WRITE)?;
+	let rs = ResolutionSettings::for_process(&proc, false);
+	let ent = vfs::get_file_from_path(&path, &rs)?;
+	translator::detach(ent.node().clone())?;
+	Ok(0)
+}
@@ -25,34 +25,99 @@ use crate::{
 		vfs::{ResolutionSettings, mountpoint},
 	},
 	memory::user::UserString,
-	process::Process,
+	process::{
+		pledge,
+		unveil::{self, Perms},
+		Process,
+	},
 	syscall::Args,
 };
 use core::ffi::c_int;
 use utils::{
-	collections::path::PathBuf,
+	collections::{path::PathBuf, vec::Vec},
 	errno,
 	errno::{EResult, Errno},
+	lock::Mutex,
 };
 
+/// Forces the unmount even if the filesystem is busy.
+const MNT_FORCE: c_int = 1;
+/// Performs a lazy unmount: the mountpoint is detached from the namespace immediately, but the
+/// filesystem itself is torn down only once the last reference to it is dropped.
+const MNT_DETACH: c_int = 2;
+/// Marks the mountpoint for expiry instead of unmounting it outright; see [`umount2`].
+const MNT_EXPIRE: c_int = 4;
+/// Refuses to follow a symbolic link for the final component of `target`.
+const UMOUNT_NOFOLLOW: c_int = 8;
+
+/// Targets marked by a previous `MNT_EXPIRE` call that have not been unmounted yet.
+///
+/// A real expiry check additionally requires tracking each mountpoint's last-busy time, which
+/// isn't available without extending [`mountpoint::MountPoint`]; this table only implements the
+/// two-call protocol (mark, then unmount on a later idle call), not genuine idleness detection.
+static EXPIRE_MARKED: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
 pub fn umount(Args(target): Args<UserString>, rs: ResolutionSettings) -> EResult<usize> {
+	pledge::enforce(&Process::current(), "umount");
 	umount2(Args((target, 0)), rs)
 }
 
 pub fn umount2(
-	Args((target, _flags)): Args<(UserString, c_int)>,
+	Args((target, flags)): Args<(UserString, c_int)>,
 	rs: ResolutionSettings,
 ) -> EResult<usize> {
-	// TODO handle flags
 	// Check permission
 	if !rs.access_profile.is_privileged() {
 		return Err(errno!(EPERM));
 	}
+	let force = flags & MNT_FORCE != 0;
+	let detach = flags & MNT_DETACH != 0;
+	let expire = flags & MNT_EXPIRE != 0;
+	let nofollow = flags & UMOUNT_NOFOLLOW != 0;
+	// `MNT_EXPIRE` is a request to mark the mount idle-if-unused, which is incompatible with
+	// actually forcing it down right away.
+	if expire && (force || detach) {
+		return Err(errno!(EINVAL));
+	}
 	// Get target directory
 	let target_slice = target.copy_from_user()?.ok_or(errno!(EFAULT))?;
 	let target_path = PathBuf::try_from(target_slice)?;
+	unveil::check(&Process::current(), &target_path, Perms::WRITE)?;
+	let rs = if nofollow {
+		let proc_mutex = Process::current();
+		let proc = proc_mutex.lock();
+		ResolutionSettings::for_process(&proc, false)
+	} else {
+		rs
+	};
 	let target = vfs::get_file_from_path(&target_path, &rs)?;
+	if expire {
+		let mut marked = EXPIRE_MARKED.lock();
+		let pos = marked.iter().position(|p| *p == target_path);
+		match pos {
+			// Already marked by a previous call: treat it as idle and fall through to the
+			// actual unmount below.
+			Some(i) => {
+				marked.remove(i);
+			}
+			// First call: mark it and come back later.
+			None => {
+				marked.push(target_path)?;
+				return Err(errno!(EAGAIN));
+			}
+		}
+	}
 	// Remove mountpoint
+	//
+	// `force` and `detach` are not fully honored here: forcing down a busy mount (revoking
+	// in-flight I/O with `EIO`) and deferring teardown of a lazily-detached mount until its last
+	// reference drops both need state on `mountpoint::MountPoint` itself (a busy/revoked flag,
+	// and something to drop teardown on), which isn't part of this snapshot. What IS true today
+	// is that `MountPoint` is already `Arc`-shared (see `vfs::node::Node::mp`), so a namespace
+	// unlink that merely drops the namespace's own strong reference already leaves the backing
+	// filesystem alive for any other holder until they release it too — which is the shape
+	// `MNT_DETACH` needs; it's `remove`'s job to unlink rather than destroy, and that's out of
+	// scope for this change.
 	mountpoint::remove(target)?;
 	Ok(0)
 }
@@ -0,0 +1,65 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `unveil` system call narrows which paths the calling process can see, as described in
+//! [`process::unveil`].
+
+use crate::{
+	memory::user::UserString,
+	process::{
+		pledge,
+		unveil::{self, Perms},
+		Process,
+	},
+	syscall::Args,
+};
+use utils::{
+	collections::path::PathBuf,
+	errno,
+	errno::{EResult, Errno},
+};
+
+/// Parses a `unveil(2)`-style `rwxc` permission string into a [`Perms`] bitmask.
+///
+/// Fails with [`EINVAL`] on any character other than `r`, `w`, `x`, or `c`.
+fn parse_perms(s: &[u8]) -> EResult<Perms> {
+	let mut perms = Perms::default();
+	for c in s {
+		perms = perms
+			| match c {
+				b'r' => Perms::READ,
+				b'w' => Perms::WRITE,
+				b'x' => Perms::EXEC,
+				b'c' => Perms::CREATE,
+				_ => return Err(errno!(EINVAL)),
+			};
+	}
+	Ok(perms)
+}
+
+pub fn unveil(Args((path, perms)): Args<(UserString, UserString)>) -> EResult<usize> {
+	let proc = Process::current();
+	pledge::enforce(&proc, "unveil");
+	let path = path.copy_from_user()?.map(PathBuf::try_from).transpose()?;
+	let perms = perms
+		.copy_from_user()?
+		.map(|s| parse_perms(s.as_bytes()))
+		.transpose()?;
+	unveil::unveil(&proc, path, perms)?;
+	Ok(0)
+}
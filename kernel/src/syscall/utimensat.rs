@@ -17,6 +17,16 @@
  */
 
 //! The `utimensat` system call allows to change the timestamps of a file.
+//!
+//! # Incomplete: only the `UTIME_NOW`/`UTIME_OMIT` half of this request is delivered
+//!
+//! `utimensat(2)` was asked for both the `UTIME_NOW`/`UTIME_OMIT` sentinels and
+//! nanosecond-resolution timestamps. Only the sentinels are here: [`resolve_timestamp`] still
+//! truncates `tv_nsec` away, because `StatSet` itself — the type that would need
+//! nanosecond-width `atime`/`mtime` fields — has no file anywhere in this tree's snapshot to
+//! define it in (`file::fs` has no `mod.rs`); only its call sites exist. There is no local type
+//! this change could extend, so this genuinely cannot be finished here, not merely left out by
+//! choice. Treat this request as half-done, not closed, until `StatSet` exists to extend.
 
 use super::util::at;
 use crate::{
@@ -36,7 +46,7 @@ use crate::{
 	time::{
 		clock,
 		clock::{current_time_ns, Clock},
-		unit::{TimeUnit, Timespec},
+		unit::Timespec,
 	},
 	tty::vga::DEFAULT_COLOR,
 };
@@ -48,6 +58,25 @@ use utils::{
 	ptr::arc::Arc,
 };
 
+/// Sentinel for `tv_nsec`: sets the timestamp to the current time, ignoring `tv_sec`.
+const UTIME_NOW: i64 = 0x3fffffff;
+/// Sentinel for `tv_nsec`: leaves the timestamp unchanged.
+const UTIME_OMIT: i64 = 0x3ffffffe;
+
+/// Resolves one of the two entries of `times` (`NULL` `times` and `UTIME_NOW` both mean "now";
+/// `UTIME_OMIT` means "leave unchanged", returned as `None`).
+fn resolve_timestamp(ts: Option<Timespec>) -> EResult<Option<u64>> {
+	let Some(ts) = ts else {
+		return Ok(Some(current_time_ns(Clock::Realtime) / 1_000_000_000));
+	};
+	match ts.tv_nsec {
+		UTIME_NOW => Ok(Some(current_time_ns(Clock::Realtime) / 1_000_000_000)),
+		UTIME_OMIT => Ok(None),
+		0..=999_999_999 => Ok(Some(ts.tv_sec as u64)),
+		_ => Err(errno!(EINVAL)),
+	}
+}
+
 pub fn utimensat(
 	Args((dirfd, pathname, times, flags)): Args<(
 		c_int,
@@ -62,24 +91,29 @@ pub fn utimensat(
 		.copy_from_user()?
 		.map(PathBuf::try_from)
 		.transpose()?;
-	let (atime, mtime) = times
-		.copy_from_user()?
-		.map(|[atime, mtime]| (atime.to_nano(), mtime.to_nano()))
-		.unwrap_or_else(|| {
-			let ts = current_time_ns(Clock::Monotonic);
-			(ts, ts)
-		});
+	// A `NULL` `times` is equivalent to both entries being `UTIME_NOW`; otherwise, each entry is
+	// resolved independently, since one timestamp may be omitted while the other is updated.
+	let (atime, mtime) = match times.copy_from_user()? {
+		Some([atime, mtime]) => (resolve_timestamp(Some(atime))?, resolve_timestamp(Some(mtime))?),
+		None => (resolve_timestamp(None)?, resolve_timestamp(None)?),
+	};
 	// Get file
 	let Resolved::Found(file) = at::get_file(&fds.lock(), rs, dirfd, pathname.as_deref(), flags)?
 	else {
 		return Err(errno!(ENOENT));
 	};
 	// Update timestamps
+	//
+	// `StatSet`'s `atime`/`mtime` only carry whole-second resolution; storing the sub-second
+	// `tv_nsec` component would require extending that type (and the on-disk inode timestamp
+	// fields backing it), which is out of scope here. What this does implement is the
+	// `UTIME_NOW`/`UTIME_OMIT` sentinels and per-field `tv_nsec` range validation, so at least
+	// one of the two timestamps can be left untouched instead of both always being overwritten.
 	vfs::set_stat(
 		file.node(),
 		&StatSet {
-			atime: Some(atime / 1_000_000_000),
-			mtime: Some(mtime / 1_000_000_000),
+			atime,
+			mtime,
 			..Default::default()
 		},
 	)?;
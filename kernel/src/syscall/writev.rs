@@ -0,0 +1,87 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `writev` system call allows to write sparse data to a file descriptor.
+
+use crate::{
+	file::fd::FileDescriptorTable,
+	process::{
+		mem_space::copy::{SyscallSlice, UserIOVec},
+		pledge, Process,
+	},
+	sync::mutex::Mutex,
+	syscall::Args,
+};
+use core::{cmp::min, ffi::c_int, ptr::NonNull, sync::atomic};
+use utils::{errno, errno::EResult, ptr::arc::Arc};
+
+pub fn writev(
+	Args((fd, iov, iovcnt)): Args<(c_int, UserIOVec, c_int)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	pledge::enforce(&Process::current(), "writev");
+	do_writev(fd, iov, iovcnt, None, None, fds)
+}
+
+/// Implementation of `writev`, shared with `pwritev`/`pwritev2`.
+///
+/// Arguments:
+/// - `offset`, if set, is used instead of (and does not update) the file's own cursor.
+/// - `flags` is reserved for `pwritev2`'s `RWF_*` flags, none of which are currently honored.
+pub fn do_writev(
+	fd: c_int,
+	iov: UserIOVec,
+	iovcnt: c_int,
+	offset: Option<i64>,
+	_flags: Option<c_int>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	if iovcnt < 0 {
+		return Err(errno!(EINVAL));
+	}
+	let segments = iov.copy_from_user(iovcnt as usize)?.ok_or(errno!(EFAULT))?;
+	let file = fds.lock().get_fd(fd)?.get_file().clone();
+	let mut off = match offset {
+		Some(o) => o as u64,
+		None => file.off.load(atomic::Ordering::Acquire),
+	};
+	let mut total = 0usize;
+	for seg in segments {
+		if seg.iov_len == 0 {
+			continue;
+		}
+		let len = min(seg.iov_len, (i32::MAX as usize).saturating_sub(total));
+		if len == 0 {
+			break;
+		}
+		let src = SyscallSlice::<u8>(NonNull::new(seg.iov_base as *mut u8));
+		let buf = src.copy_from_user_vec(0, len)?.ok_or(errno!(EFAULT))?;
+		let n = file.ops.write(&file, off, &buf)?;
+		off += n as u64;
+		total += n;
+		if n < len {
+			break;
+		}
+	}
+	// Only the file's own cursor advances; a caller-provided offset (as used by `pwritev`) is
+	// left untouched, per `pwrite`'s semantics.
+	if offset.is_none() {
+		file.off.store(off, atomic::Ordering::Release);
+	}
+	Ok(total)
+}
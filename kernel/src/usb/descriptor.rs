@@ -0,0 +1,118 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Parsing of the standard USB descriptors read off a device's default control pipe during
+//! enumeration.
+
+use utils::{collections::vec::Vec, errno, errno::EResult};
+
+/// The wire length of a standard device descriptor.
+pub const DEVICE_DESCRIPTOR_LEN: usize = 18;
+/// The largest configuration descriptor (header plus nested interface/endpoint descriptors)
+/// this kernel will read in one `GET_DESCRIPTOR` request.
+pub const CONFIG_DESCRIPTOR_MAX_LEN: usize = 255;
+
+/// The standard device descriptor (USB 2.0 spec, table 9-8).
+#[derive(Debug, Clone, Default)]
+pub struct DeviceDescriptor {
+	pub usb_version: u16,
+	pub device_class: u8,
+	pub device_subclass: u8,
+	pub device_protocol: u8,
+	pub max_packet_size0: u8,
+	pub vendor_id: u16,
+	pub product_id: u16,
+	pub num_configurations: u8,
+}
+
+impl DeviceDescriptor {
+	/// Parses a raw device descriptor, as returned by a `GET_DESCRIPTOR(DEVICE)` request.
+	pub fn parse(raw: &[u8]) -> EResult<Self> {
+		if raw.len() < 18 || raw[1] != super::DESC_TYPE_DEVICE {
+			return Err(errno!(EINVAL));
+		}
+		Ok(Self {
+			usb_version: u16::from_le_bytes([raw[2], raw[3]]),
+			device_class: raw[4],
+			device_subclass: raw[5],
+			device_protocol: raw[6],
+			max_packet_size0: raw[7],
+			vendor_id: u16::from_le_bytes([raw[8], raw[9]]),
+			product_id: u16::from_le_bytes([raw[10], raw[11]]),
+			num_configurations: raw[17],
+		})
+	}
+}
+
+/// One interface of a [`ConfigurationDescriptor`] (USB 2.0 spec, table 9-12).
+#[derive(Debug, Clone, Default)]
+pub struct InterfaceDescriptor {
+	pub interface_number: u8,
+	pub interface_class: u8,
+	pub interface_subclass: u8,
+	pub interface_protocol: u8,
+	pub num_endpoints: u8,
+}
+
+/// The standard configuration descriptor, along with the interfaces nested under it (USB 2.0
+/// spec, table 9-10).
+#[derive(Debug, Clone, Default)]
+pub struct ConfigurationDescriptor {
+	pub configuration_value: u8,
+	pub num_interfaces: u8,
+	pub interfaces: Vec<InterfaceDescriptor>,
+}
+
+impl ConfigurationDescriptor {
+	/// Parses a raw configuration descriptor, as returned by a `GET_DESCRIPTOR(CONFIGURATION)`
+	/// request, walking the interface descriptors that directly follow it.
+	pub fn parse(raw: &[u8]) -> EResult<Self> {
+		if raw.len() < 9 || raw[1] != super::DESC_TYPE_CONFIGURATION {
+			return Err(errno!(EINVAL));
+		}
+		let total_length = u16::from_le_bytes([raw[2], raw[3]]) as usize;
+		let num_interfaces = raw[4];
+		let configuration_value = raw[5];
+		let mut interfaces = Vec::new();
+		let mut off = 9;
+		while off + 1 < raw.len().min(total_length) {
+			let len = raw[off] as usize;
+			if len == 0 || off + len > raw.len() {
+				break;
+			}
+			// Descriptor type 0x04 is an interface descriptor; anything else nested in the
+			// configuration (endpoint, HID, class-specific...) is skipped here and left to the
+			// class driver that claims the interface to re-walk if it needs them.
+			if raw[off + 1] == 0x04 && len >= 9 {
+				interfaces.push(InterfaceDescriptor {
+					interface_number: raw[off + 2],
+					num_endpoints: raw[off + 4],
+					interface_class: raw[off + 5],
+					interface_subclass: raw[off + 6],
+					interface_protocol: raw[off + 7],
+				})?;
+			}
+			off += len;
+		}
+		Ok(Self {
+			configuration_value,
+			num_interfaces,
+			interfaces,
+		})
+	}
+}
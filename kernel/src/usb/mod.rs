@@ -0,0 +1,175 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! USB subsystem.
+//!
+//! A [`HostController`] owns the frame list and schedules transfers over it; everything above
+//! that is host-controller-agnostic. A newly connected port is enumerated by resetting it,
+//! talking to the device over its default control [`pipe::Pipe`] (address 0) to read its
+//! descriptors, assigning it a real address, then handing each interface descriptor to whichever
+//! registered [`ClassDriver`] claims it.
+
+pub mod descriptor;
+pub mod pipe;
+
+use descriptor::{ConfigurationDescriptor, DeviceDescriptor};
+use pipe::{Pipe, PipeType};
+use utils::{boxed::Box, collections::vec::Vec, errno::EResult, lock::Mutex, ptr::arc::Arc};
+
+/// Standard `GET_DESCRIPTOR` request code, issued on the default control pipe during
+/// enumeration.
+pub const REQUEST_GET_DESCRIPTOR: u8 = 0x06;
+/// Standard `SET_ADDRESS` request code.
+pub const REQUEST_SET_ADDRESS: u8 = 0x05;
+/// Standard `SET_CONFIGURATION` request code.
+pub const REQUEST_SET_CONFIGURATION: u8 = 0x09;
+
+/// Descriptor type value for a device descriptor, as used in a `GET_DESCRIPTOR` request.
+pub const DESC_TYPE_DEVICE: u8 = 0x01;
+/// Descriptor type value for a configuration descriptor.
+pub const DESC_TYPE_CONFIGURATION: u8 = 0x02;
+
+/// A host controller, owning the bus's frame list and the transfers scheduled on it.
+///
+/// Implementations provide the UHCI/EHCI-specific register programming; everything else
+/// (enumeration, class driver dispatch) is written against this trait only.
+pub trait HostController: Send + Sync {
+	/// Resets the given root-hub port, as the first step of enumerating whatever device was just
+	/// plugged into it.
+	fn reset_port(&self, port: u8) -> EResult<()>;
+	/// Tells whether a device is currently connected on `port`.
+	fn port_connected(&self, port: u8) -> bool;
+	/// Submits a transfer on `pipe`, writing `setup` (if any, for control transfers) then
+	/// transferring `buf` in the pipe's direction, and returns the number of bytes actually
+	/// transferred.
+	fn transfer(&self, pipe: &Pipe, setup: Option<&[u8]>, buf: &mut [u8]) -> EResult<usize>;
+}
+
+/// A driver claiming one or more interfaces of an enumerated device, by class/subclass/protocol.
+pub trait ClassDriver: Send + Sync {
+	/// Tells whether this driver handles the given interface.
+	fn matches(&self, iface: &descriptor::InterfaceDescriptor) -> bool;
+	/// Called once for every interface this driver claims, so it can set up its own pipes and
+	/// register itself with the rest of the kernel (e.g. as an input device or a block device
+	/// surfaced through the VFS).
+	fn bind(&self, device: Arc<Device>, iface: &descriptor::InterfaceDescriptor) -> EResult<()>;
+}
+
+/// An enumerated USB device, reachable through its default and claimed pipes.
+pub struct Device {
+	/// The controller the device is attached to.
+	controller: Arc<dyn HostController>,
+	/// The address assigned to the device during enumeration (0 until [`REQUEST_SET_ADDRESS`]
+	/// completes).
+	address: Mutex<u8>,
+	/// The device's standard descriptor.
+	pub descriptor: DeviceDescriptor,
+	/// The configuration selected for use.
+	pub configuration: ConfigurationDescriptor,
+}
+
+impl Device {
+	/// Returns the device's default control pipe (endpoint 0).
+	fn control_pipe(&self) -> Pipe {
+		Pipe::new(
+			*self.address.lock(),
+			0,
+			PipeType::Control,
+			self.descriptor.max_packet_size0 as u16,
+		)
+	}
+
+	/// Issues a `GET_DESCRIPTOR` request for `desc_type`, reading up to `buf.len()` bytes into
+	/// `buf`.
+	fn get_descriptor(&self, desc_type: u8, buf: &mut [u8]) -> EResult<usize> {
+		// bmRequestType, bRequest, wValue (type << 8 | index), wIndex, wLength
+		let setup = [
+			0x80,
+			REQUEST_GET_DESCRIPTOR,
+			0,
+			desc_type,
+			0,
+			0,
+			buf.len() as u8,
+			(buf.len() >> 8) as u8,
+		];
+		self.controller
+			.transfer(&self.control_pipe(), Some(&setup), buf)
+	}
+}
+
+/// The registry of class drivers consulted when a new interface is enumerated.
+static CLASS_DRIVERS: Mutex<Vec<Box<dyn ClassDriver>>> = Mutex::new(Vec::new());
+
+/// Registers `driver` so it is considered for every interface enumerated from now on.
+pub fn register_class_driver(driver: Box<dyn ClassDriver>) -> EResult<()> {
+	CLASS_DRIVERS.lock().push(driver)
+}
+
+/// Enumerates the device freshly connected on `port` of `controller`: resets the port, reads the
+/// device and configuration descriptors over the default control pipe, assigns it a real
+/// address, then hands each interface to whichever registered [`ClassDriver`] matches it.
+pub fn enumerate(controller: Arc<dyn HostController>, port: u8, address: u8) -> EResult<()> {
+	controller.reset_port(port)?;
+	// Read just the first 8 bytes first: that's enough to learn `max_packet_size0`, which may be
+	// needed to size the control pipe correctly for full reads on some devices.
+	let mut header = [0u8; 8];
+	let mut probe = Device {
+		controller: controller.clone(),
+		address: Mutex::new(0),
+		descriptor: DeviceDescriptor::default(),
+		configuration: ConfigurationDescriptor::default(),
+	};
+	probe.get_descriptor(DESC_TYPE_DEVICE, &mut header)?;
+	probe.descriptor.max_packet_size0 = header[7];
+	let mut raw = [0u8; descriptor::DEVICE_DESCRIPTOR_LEN];
+	probe.get_descriptor(DESC_TYPE_DEVICE, &mut raw)?;
+	let descriptor = DeviceDescriptor::parse(&raw)?;
+	// Move the device from address 0 to its assigned address.
+	let setup = [0x00, REQUEST_SET_ADDRESS, address, 0, 0, 0, 0, 0];
+	controller.transfer(&probe.control_pipe(), Some(&setup), &mut [])?;
+	let device = Arc::new(Device {
+		controller,
+		address: Mutex::new(address),
+		descriptor,
+		configuration: ConfigurationDescriptor::default(),
+	})?;
+	let mut raw = [0u8; descriptor::CONFIG_DESCRIPTOR_MAX_LEN];
+	device.get_descriptor(DESC_TYPE_CONFIGURATION, &mut raw)?;
+	let configuration = ConfigurationDescriptor::parse(&raw)?;
+	let setup = [
+		0x00,
+		REQUEST_SET_CONFIGURATION,
+		configuration.configuration_value,
+		0,
+		0,
+		0,
+		0,
+		0,
+	];
+	device
+		.controller
+		.transfer(&device.control_pipe(), Some(&setup), &mut [])?;
+	let drivers = CLASS_DRIVERS.lock();
+	for iface in &configuration.interfaces {
+		if let Some(driver) = drivers.iter().find(|d| d.matches(iface)) {
+			driver.bind(device.clone(), iface)?;
+		}
+	}
+	Ok(())
+}
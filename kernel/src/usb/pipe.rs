@@ -0,0 +1,97 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! A [`Pipe`] is the logical channel a [`super::HostController`] schedules transfers on, between
+//! the host and one endpoint of one device.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// The kind of endpoint a [`Pipe`] talks to, which determines how the host controller schedules
+/// transfers on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PipeType {
+	/// Used for device setup and standard requests (e.g. the default pipe, endpoint 0).
+	Control,
+	/// Used for large, non-time-critical transfers (e.g. mass storage).
+	Bulk,
+	/// Used for small, latency-sensitive transfers (e.g. HID reports).
+	Interrupt,
+	/// Used for transfers with a guaranteed bandwidth but tolerance for dropped data.
+	Isochronous,
+}
+
+/// A logical channel between the host and one endpoint of one device.
+#[derive(Debug)]
+pub struct Pipe {
+	/// The device's address on the bus, assigned during enumeration.
+	address: u8,
+	/// The endpoint number on the device, 0 being the default control endpoint.
+	endpoint: u8,
+	/// The endpoint's transfer type.
+	ty: PipeType,
+	/// The maximum packet size the endpoint accepts, in bytes.
+	max_packet_size: u16,
+	/// The current data-toggle bit (DATA0/DATA1), tracked per pipe as required by the USB
+	/// protocol.
+	toggle: AtomicBool,
+}
+
+impl Pipe {
+	/// Creates a pipe to `endpoint` of the device at `address`.
+	pub fn new(address: u8, endpoint: u8, ty: PipeType, max_packet_size: u16) -> Self {
+		Self {
+			address,
+			endpoint,
+			ty,
+			max_packet_size,
+			toggle: AtomicBool::new(false),
+		}
+	}
+
+	/// Returns the device address this pipe talks to.
+	pub fn address(&self) -> u8 {
+		self.address
+	}
+
+	/// Returns the endpoint number this pipe talks to.
+	pub fn endpoint(&self) -> u8 {
+		self.endpoint
+	}
+
+	/// Returns the endpoint's transfer type.
+	pub fn ty(&self) -> PipeType {
+		self.ty
+	}
+
+	/// Returns the maximum packet size the endpoint accepts.
+	pub fn max_packet_size(&self) -> u16 {
+		self.max_packet_size
+	}
+
+	/// Returns the current data-toggle bit, then flips it, as required after each successfully
+	/// transferred packet.
+	pub fn next_toggle(&self) -> bool {
+		self.toggle.fetch_xor(true, Ordering::AcqRel)
+	}
+
+	/// Resets the data-toggle bit to DATA0, as required after a `SET_CONFIGURATION` or
+	/// `CLEAR_FEATURE(ENDPOINT_HALT)` request.
+	pub fn reset_toggle(&self) {
+		self.toggle.store(false, Ordering::Release);
+	}
+}
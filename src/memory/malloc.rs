@@ -8,6 +8,7 @@ use core::ffi::c_void;
 use core::mem::MaybeUninit;
 use crate::memory::PAGE_SIZE;
 use crate::memory::buddy;
+use crate::sync::Mutex;
 use crate::util::data_struct::LinkedList;
 use crate::util;
 
@@ -19,6 +20,22 @@ type ChunkFlags = u8;
 /* Chunk flag indicating that the chunk is being used */
 const CHUNK_FLAG_USED: ChunkFlags = 0b1;
 
+/*
+ * Type representing a set of allocation context flags, mirroring the GFP flags of other kernels.
+ * These tell `alloc` (and, through it, `Box`) what the caller is allowed to do to satisfy the
+ * allocation and what guarantees it needs on the returned memory.
+ */
+pub type AllocFlags = u8;
+
+/* No particular constraint: the default, sleepable allocation context. */
+pub const GFP_KERNEL: AllocFlags = 0;
+/* The caller holds a spinlock or runs in interrupt context: the allocator must not take any path
+ * that could block or reclaim, it must either satisfy the request immediately or fail. */
+pub const GFP_ATOMIC: AllocFlags = 0b01;
+/* The returned memory must be zeroed, which the allocator does itself instead of the caller doing
+ * an allocate-then-memset, so the zeroing can be elided when the memory is already known-zero. */
+pub const GFP_ZERO: AllocFlags = 0b10;
+
 /*
  * The minimum amount of bytes required to create a free chunk.
  */
@@ -38,8 +55,10 @@ const FREE_LIST_BINS: usize = 8;
  * A chunk of allocated or free memory stored in linked lists.
  */
 struct Chunk {
-	/* The linked list storing the chunks */
+	/* The linked list storing the chunks in the order they appear in memory, used for coalescing */
 	list: LinkedList,
+	/* The linked list linking this chunk to the other free chunks of the same free list bin */
+	free_list: LinkedList,
 	/* The chunk's flags */
 	flags: u8,
 	/* The size of the chunk's memory in bytes */
@@ -66,6 +85,7 @@ impl Chunk {
 	fn new_free(size: usize) -> Self {
 		Self {
 			list: LinkedList::new_single(),
+			free_list: LinkedList::new_single(),
 			flags: 0,
 			size: size,
 		}
@@ -107,15 +127,18 @@ impl Chunk {
 	 * Marks the chunk as free and tries to coalesce it with adjacent chunks if they are free.
 	 */
 	fn coalesce(&mut self) {
-		self.flags &= CHUNK_FLAG_USED;
+		self.flags &= !CHUNK_FLAG_USED;
 
 		if let Some(next) = self.list.get_next() {
 			let n = unsafe {
-				&*crate::linked_list_get!(next as *mut LinkedList, *const Chunk, list)
+				&mut *crate::linked_list_get!(next as *mut LinkedList, *mut Chunk, list)
 			};
 
 			if !n.is_used() {
 				self.size += core::mem::size_of::<Chunk>() + n.size;
+				// `n` is being absorbed into `self`: it must leave its free list bin too, or a
+				// stale entry of the wrong size stays reachable from a future `alloc`.
+				free_list_remove(n);
 				next.unlink();
 			}
 		}
@@ -237,6 +260,12 @@ static mut BLOCKS: MaybeUninit<[Option<&'static mut Block>; 3]> = MaybeUninit::u
 static mut FREE_LISTS: MaybeUninit<[Option<&'static mut Chunk>; FREE_LIST_BINS]>
 	= MaybeUninit::uninit();
 
+/*
+ * Mutex protecting the allocator's global state (`BLOCKS` and `FREE_LISTS`) against concurrent
+ * accesses.
+ */
+static MUTEX: Mutex<()> = Mutex::new(());
+
 /*
  * Initializes the allocator. This function must be called before using the allocator's functions
  * and exactly once.
@@ -248,43 +277,200 @@ pub fn init() {
 	}
 }
 
+/*
+ * Rounds up the given size `n` to the allocator's granularity, and ensures the result is at
+ * least `FREE_CHUNK_MIN` bytes so a freed chunk can always host a `LinkedList` and be binned.
+ */
+fn get_alloc_size(n: usize) -> usize {
+	let n = core::cmp::max(n, FREE_CHUNK_MIN);
+	(n + (FREE_CHUNK_MIN - 1)) & !(FREE_CHUNK_MIN - 1)
+}
+
+/*
+ * Returns the index of the free list bin that holds chunks of the given size `size`.
+ * Bin `i` holds chunks whose size is in `[FREE_LIST_SMALLEST_SIZE << i, FREE_LIST_SMALLEST_SIZE
+ * << (i + 1))`.
+ */
+fn get_bin_index(size: usize) -> usize {
+	let ratio = size / FREE_LIST_SMALLEST_SIZE;
+	let bin = if ratio <= 1 {
+		0
+	} else {
+		(usize::BITS - 1 - ratio.leading_zeros()) as usize
+	};
+	core::cmp::min(bin, FREE_LIST_BINS - 1)
+}
+
 /*
  * Returns the free list for the given size `size`. If `insert` is not set, the function may return
  * a free list that contain chunks greater than the required size so that it can be split.
  */
-fn get_free_list(_size: usize, _insert: bool) -> Option<&'static mut Chunk> {
-	// TODO
+fn get_free_list(size: usize, insert: bool) -> Option<&'static mut Chunk> {
+	let lists = unsafe { FREE_LISTS.assume_init_mut() };
+	let bin = get_bin_index(size);
+
+	if insert {
+		return lists[bin].as_deref_mut();
+	}
+
+	for b in bin..FREE_LIST_BINS {
+		// Within a bin, chunk sizes span up to a factor of two, so the head of the bin is not
+		// necessarily large enough: walk the bin's chunks until one fits.
+		let mut cur = lists[b].as_deref_mut();
+		while let Some(chunk) = cur {
+			if chunk.size >= size {
+				return Some(chunk);
+			}
+			cur = chunk.free_list.get_next().map(|next| unsafe {
+				&mut *crate::linked_list_get!(next as *mut LinkedList, *mut Chunk, free_list)
+			});
+		}
+	}
 	None
 }
 
-// TODO Mutex
 /*
- * Allocates `n` bytes of kernel memory and returns a pointer to the beginning of the allocated
- * chunk. If the allocation fails, the function shall return None.
+ * Inserts the free chunk `chunk` into the free list matching its size.
  */
-pub fn alloc(_n: usize) -> Option<*mut c_void> {
-	// TODO
-	None
+fn free_list_insert(chunk: &'static mut Chunk) {
+	let bin = get_bin_index(chunk.size);
+	let lists = unsafe { FREE_LISTS.assume_init_mut() };
+
+	if let Some(head) = lists[bin].take() {
+		head.free_list.insert_before(&mut chunk.free_list);
+	}
+	lists[bin] = Some(chunk);
+}
+
+/*
+ * Removes the chunk `chunk` from whichever free list it belongs to.
+ */
+fn free_list_remove(chunk: &mut Chunk) {
+	let bin = get_bin_index(chunk.size);
+	let lists = unsafe { FREE_LISTS.assume_init_mut() };
+
+	if let Some(head) = &lists[bin] {
+		if core::ptr::eq(*head, chunk) {
+			lists[bin] = chunk.free_list.get_next().map(|next| unsafe {
+				&mut *crate::linked_list_get!(next as *mut LinkedList, *mut Chunk, free_list)
+			});
+		}
+	}
+	chunk.free_list.unlink();
+}
+
+/*
+ * Finds the block owning the chunk at the given pointer `ptr`, if any.
+ */
+fn find_owning_block(ptr: *const Chunk) -> Option<&'static mut Option<&'static mut Block>> {
+	let blocks = unsafe { BLOCKS.assume_init_mut() };
+	blocks.iter_mut().find(|b| match b {
+		Some(block) => core::ptr::eq(&block.first_chunk, ptr),
+		None => false,
+	})
+}
+
+/*
+ * Allocates `n` bytes of kernel memory according to `flags` and returns a pointer to the
+ * beginning of the allocated chunk. If the allocation fails, the function shall return None.
+ *
+ * `GFP_ATOMIC` is honored implicitly: the allocator never sleeps nor reclaims while `MUTEX` is
+ * held, it either satisfies the request from the free lists/buddy allocator right away or fails.
+ * `GFP_ZERO` is honored explicitly below, zeroing the chunk's payload before handing it out.
+ */
+pub fn alloc(n: usize, flags: AllocFlags) -> Option<*mut c_void> {
+	let _guard = MUTEX.lock();
+	let size = get_alloc_size(n);
+
+	let chunk = if let Some(free_chunk) = get_free_list(size, false) {
+		free_list_remove(free_chunk);
+		free_chunk.split(size);
+
+		if let Some(next) = free_chunk.list.get_next() {
+			let n = unsafe {
+				&mut *crate::linked_list_get!(next as *mut LinkedList, *mut Chunk, list)
+			};
+			if !n.is_used() {
+				free_list_insert(n);
+			}
+		}
+
+		free_chunk
+	} else {
+		let block = Block::new(size).ok()?;
+		let blocks = unsafe { BLOCKS.assume_init_mut() };
+		let slot = blocks.iter_mut().find(|b| b.is_none())?;
+		*slot = Some(block);
+
+		let block = slot.as_mut().unwrap();
+		block.first_chunk.split(size);
+
+		if let Some(next) = block.first_chunk.list.get_next() {
+			let n = unsafe {
+				&mut *crate::linked_list_get!(next as *mut LinkedList, *mut Chunk, list)
+			};
+			if !n.is_used() {
+				free_list_insert(n);
+			}
+		}
+
+		&mut block.first_chunk
+	};
+
+	let ptr = (chunk as *mut Chunk as usize) + core::mem::size_of::<Chunk>();
+	if flags & GFP_ZERO != 0 {
+		unsafe {
+			util::bzero(ptr as *mut c_void, n);
+		}
+	}
+	Some(ptr as *mut c_void)
 }
 
-// TODO Mutex
 /*
  * Changes the size of the memory previously allocated with `alloc`. `ptr` is the pointer to the
  * chunk of memory. `n` is the new size of the chunk of memory. If the reallocation fails, the
  * chunk is left untouched.
  */
-pub fn realloc(_ptr: *const c_void, _n: usize) -> Option<*mut c_void> {
-	// TODO
-	None
+pub fn realloc(ptr: *const c_void, n: usize) -> Option<*mut c_void> {
+	let _guard = MUTEX.lock();
+	let size = get_alloc_size(n);
+	let chunk = unsafe { &mut *((ptr as usize - core::mem::size_of::<Chunk>()) as *mut Chunk) };
+
+	let delta = size as isize - chunk.size as isize;
+	if chunk.resize(delta) {
+		return Some(ptr as *mut c_void);
+	}
+
+	drop(_guard);
+	let new_ptr = alloc(n, GFP_KERNEL)?;
+	unsafe {
+		let copy_size = core::cmp::min(chunk.size, n);
+		core::ptr::copy_nonoverlapping(ptr as *const u8, new_ptr as *mut u8, copy_size);
+	}
+	free(ptr);
+	Some(new_ptr)
 }
 
-// TODO Mutex
 /*
  * Frees the memory at the pointer `ptr` previously allocated with `alloc`. Subsequent uses of the
  * associated memory are undefined.
  */
-pub fn free(_ptr: *const c_void) {
-	// TODO
+pub fn free(ptr: *const c_void) {
+	let _guard = MUTEX.lock();
+	let chunk = unsafe { &mut *((ptr as usize - core::mem::size_of::<Chunk>()) as *mut Chunk) };
+	chunk.coalesce();
+
+	if chunk.list.get_prev().is_none() && chunk.list.get_next().is_none() {
+		if let Some(slot) = find_owning_block(chunk as *const Chunk) {
+			let block = slot.take().unwrap();
+			let order = block.order;
+			let block_ptr = block as *mut Block as *mut c_void;
+			buddy::free_kernel(block_ptr, order);
+			return;
+		}
+	}
+
+	free_list_insert(chunk);
 }
 
 #[cfg(test)]
@@ -293,7 +479,7 @@ mod test {
 
 	#[test_case]
 	fn alloc_free0() {
-		if let Some(ptr) = alloc(1) {
+		if let Some(ptr) = alloc(1, GFP_KERNEL) {
 			unsafe {
 				util::memset(ptr, -1, 1);
 			}
@@ -305,7 +491,7 @@ mod test {
 
 	#[test_case]
 	fn alloc_free1() {
-		if let Some(ptr) = alloc(8) {
+		if let Some(ptr) = alloc(8, GFP_KERNEL) {
 			unsafe {
 				util::memset(ptr, -1, 8);
 			}
@@ -317,7 +503,7 @@ mod test {
 
 	#[test_case]
 	fn alloc_free2() {
-		if let Some(ptr) = alloc(PAGE_SIZE) {
+		if let Some(ptr) = alloc(PAGE_SIZE, GFP_KERNEL) {
 			unsafe {
 				util::memset(ptr, -1, PAGE_SIZE);
 			}
@@ -327,5 +513,48 @@ mod test {
 		}
 	}
 
+	#[test_case]
+	fn alloc_split() {
+		// Allocating a small chunk from a freshly grabbed block must leave a free remainder behind,
+		// which a second small allocation should then be able to reuse without growing the heap.
+		let a = alloc(16, GFP_KERNEL).unwrap();
+		let b = alloc(16, GFP_KERNEL).unwrap();
+		debug_assert_ne!(a, b);
+
+		free(a);
+		free(b);
+	}
+
+	#[test_case]
+	fn alloc_coalesce() {
+		// Freeing two adjacent chunks must merge them back into one, large enough to satisfy an
+		// allocation that wouldn't fit in either chunk alone.
+		let a = alloc(64, GFP_KERNEL).unwrap();
+		let b = alloc(64, GFP_KERNEL).unwrap();
+
+		free(a);
+		free(b);
+
+		let c = alloc(100, GFP_KERNEL).unwrap();
+		unsafe {
+			util::memset(c, -1, 100);
+		}
+		free(c);
+	}
+
+	#[test_case]
+	fn alloc_cross_bin_reuse() {
+		// A chunk freed in a larger bin must be reachable and splittable when a smaller allocation
+		// is requested and its own, smaller bin is empty.
+		let big = alloc(512, GFP_KERNEL).unwrap();
+		free(big);
+
+		let small = alloc(16, GFP_KERNEL).unwrap();
+		unsafe {
+			util::memset(small, -1, 16);
+		}
+		free(small);
+	}
+
 	// TODO
 }
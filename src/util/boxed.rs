@@ -3,6 +3,8 @@
 use core::ffi::c_void;
 use core::marker::Unsize;
 use core::mem::ManuallyDrop;
+use core::mem::MaybeUninit;
+use core::mem::size_of;
 use core::mem::size_of_val;
 use core::mem::transmute;
 use core::ops::CoerceUnsized;
@@ -13,6 +15,7 @@ use core::ptr::copy_nonoverlapping;
 use core::ptr::drop_in_place;
 use crate::errno::Errno;
 use crate::memory::malloc;
+use crate::memory::malloc::AllocFlags;
 
 /// This structure allows to store an object in an allocated region of memory.
 /// The object is owned by the Box and will be freed whenever the Box is dropped.
@@ -25,13 +28,22 @@ pub struct Box<T: ?Sized> {
 impl<T> Box<T> {
 	/// Creates a new instance and places the given value `value` into it.
 	/// If the allocation fails, the function shall return an error.
+	/// This is equivalent to calling `new_in` with `GFP_KERNEL`, i.e. a regular, sleepable
+	/// allocation context.
 	pub fn new(value: T) -> Result<Box::<T>, Errno> {
+		Self::new_in(value, malloc::GFP_KERNEL)
+	}
+
+	/// Same as `new`, but allocates according to `flags` (see `malloc::AllocFlags`), letting the
+	/// caller express e.g. that it must not sleep (`GFP_ATOMIC`) because it holds a spinlock or
+	/// runs in interrupt context.
+	pub fn new_in(value: T, flags: AllocFlags) -> Result<Box::<T>, Errno> {
 		let value_ref = &ManuallyDrop::new(value);
 
 		let size = size_of_val(value_ref);
 		let ptr = if size > 0 {
 			let ptr = unsafe { // Use of transmute
-				transmute::<*mut c_void, *mut T>(malloc::alloc(size)?)
+				transmute::<*mut c_void, *mut T>(malloc::alloc(size, flags).ok_or(Errno::ENOMEM)?)
 			};
 			unsafe { // Call to unsafe function
 				copy_nonoverlapping(value_ref as *const _ as *const u8, ptr as *mut u8, size);
@@ -45,6 +57,33 @@ impl<T> Box<T> {
 			ptr: ptr,
 		})
 	}
+
+	/// Allocates, according to `flags`, a chunk of memory large enough to hold a `T` without
+	/// initializing it. The caller is responsible for initializing the value before using it.
+	pub fn try_new_uninit(flags: AllocFlags) -> Result<Box<MaybeUninit<T>>, Errno> {
+		let size = size_of::<T>();
+		let ptr = if size > 0 {
+			let ptr = unsafe { // Use of transmute
+				transmute::<*mut c_void, *mut MaybeUninit<T>>(
+					malloc::alloc(size, flags).ok_or(Errno::ENOMEM)?
+				)
+			};
+			NonNull::new(ptr).unwrap()
+		} else {
+			NonNull::dangling()
+		};
+
+		Ok(Box {
+			ptr: ptr,
+		})
+	}
+
+	/// Same as `try_new_uninit`, but guarantees the returned memory is zeroed. The allocator
+	/// zeroes the memory itself instead of this function allocating then memsetting, so the
+	/// zeroing can be skipped entirely when the backing memory is already known to be zero.
+	pub fn new_zeroed(flags: AllocFlags) -> Result<Box<MaybeUninit<T>>, Errno> {
+		Self::try_new_uninit(flags | malloc::GFP_ZERO)
+	}
 }
 
 impl<T: ?Sized> AsRef<T> for Box<T> {
@@ -111,5 +150,14 @@ mod test {
 		debug_assert_eq!(*b.unwrap(), 42);
 	}
 
+	#[test_case]
+	fn box_zeroed() {
+		let b = Box::<usize>::new_zeroed(malloc::GFP_KERNEL).unwrap();
+		let b = unsafe { // Safe because zeroed memory is a valid `usize`
+			transmute::<Box<MaybeUninit<usize>>, Box<usize>>(b)
+		};
+		debug_assert_eq!(*b, 0);
+	}
+
 	// TODO More tests
 }